@@ -0,0 +1,323 @@
+use std::collections::{HashMap, HashSet};
+
+use nannou::{math::Vec2Rotate, prelude::Vec2};
+
+use crate::segment::Segment;
+
+/// Basis vectors of the lattice a curve is expected to be drawn on
+#[derive(Debug, Copy, Clone)]
+pub struct Lattice {
+    u: Vec2,
+    v: Vec2,
+}
+
+impl Lattice {
+    pub fn new(u: Vec2, v: Vec2) -> Self {
+        assert!(
+            u.perp_dot(v).abs() > f32::EPSILON,
+            "lattice basis is degenerate"
+        );
+        Lattice { u, v }
+    }
+
+    /// Square grid with the given spacing
+    pub fn square(step: f32) -> Self {
+        Lattice::new(Vec2::new(step, 0.0), Vec2::new(0.0, step))
+    }
+
+    /// Triangular grid with one edge along the given vector, the other at 60 degrees to it
+    pub fn triangular(edge: Vec2) -> Self {
+        Lattice::new(edge, edge.rotate(std::f32::consts::FRAC_PI_3))
+    }
+
+    /// Coordinates of the nearest lattice point
+    fn coords(&self, p: Vec2) -> (i32, i32) {
+        let det = self.u.perp_dot(self.v);
+        let i = p.perp_dot(self.v) / det;
+        let j = self.u.perp_dot(p) / det;
+        (i.round() as i32, j.round() as i32)
+    }
+
+    fn point(&self, c: (i32, i32)) -> Vec2 {
+        self.u * c.0 as f32 + self.v * c.1 as f32
+    }
+
+    /// How far a point may be from a lattice point while still counting as on the lattice
+    fn tolerance(&self) -> f32 {
+        self.u.length().min(self.v.length()) * 0.1
+    }
+}
+
+/// How two segments meet when they are not simply consecutive pieces of the path
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Contact {
+    /// The interiors cross at a single point
+    Crossing,
+    /// An endpoint of one lies in the interior of the other
+    Touch,
+    /// The segments are collinear and share more than a point
+    Overlap,
+}
+
+/// Lattice points visited by the curve out of those in the parallelogram spanned by its extremes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    pub visited: usize,
+    pub total: usize,
+}
+
+impl Coverage {
+    pub fn ratio(&self) -> f32 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.visited as f32 / self.total as f32
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.visited == self.total
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CurveReport {
+    /// Number of segments analyzed
+    pub segments: usize,
+    /// Indices of segments that do not start where the previous one ended
+    pub gaps: Vec<usize>,
+    /// Pairs of segment indices that meet somewhere other than a shared vertex of the path
+    pub contacts: Vec<(usize, usize, Contact)>,
+    /// Vertices of the path that are reached more than once
+    pub revisited: Vec<Vec2>,
+    /// Vertices that do not lie on the lattice
+    pub off_lattice: Vec<Vec2>,
+    pub coverage: Coverage,
+}
+
+impl CurveReport {
+    pub fn crossings(&self) -> impl Iterator<Item = &(usize, usize, Contact)> {
+        self.contacts.iter().filter(|c| c.2 != Contact::Overlap)
+    }
+
+    pub fn overlaps(&self) -> impl Iterator<Item = &(usize, usize, Contact)> {
+        self.contacts.iter().filter(|c| c.2 == Contact::Overlap)
+    }
+
+    /// A single unbroken path that never meets itself
+    pub fn is_simple_path(&self) -> bool {
+        self.segments > 0
+            && self.gaps.is_empty()
+            && self.contacts.is_empty()
+            && self.revisited.is_empty()
+    }
+
+    /// A simple path on the lattice that visits every lattice point in its extent
+    pub fn is_space_filling(&self) -> bool {
+        self.is_simple_path() && self.off_lattice.is_empty() && self.coverage.is_complete()
+    }
+}
+
+fn orientation(a: Vec2, b: Vec2, p: Vec2, eps: f32) -> i8 {
+    let cross = (b - a).perp_dot(p - a);
+    if cross > eps {
+        1
+    } else if cross < -eps {
+        -1
+    } else {
+        0
+    }
+}
+
+/// Classify how two segments meet, ignoring a single shared endpoint
+pub fn contact(a: &Segment, b: &Segment, eps: f32) -> Option<Contact> {
+    let (p, p2, q, q2) = (a.start(), a.end(), b.start(), b.end());
+    let scale = a.length().max(b.length()).max(f32::EPSILON);
+    let area_eps = eps * scale;
+
+    let o1 = orientation(p, p2, q, area_eps);
+    let o2 = orientation(p, p2, q2, area_eps);
+    let o3 = orientation(q, q2, p, area_eps);
+    let o4 = orientation(q, q2, p2, area_eps);
+
+    if o1 == 0 && o2 == 0 && o3 == 0 && o4 == 0 {
+        // Collinear so project onto the direction of a and compare the intervals
+        let dir = (p2 - p) / scale;
+        let (a0, a1) = (0.0_f32, (p2 - p).dot(dir));
+        let (b0, b1) = {
+            let (s, t) = ((q - p).dot(dir), (q2 - p).dot(dir));
+            (s.min(t), s.max(t))
+        };
+        let shared = a1.min(b1) - a0.max(b0);
+        return if shared > eps {
+            Some(Contact::Overlap)
+        } else {
+            None
+        };
+    }
+
+    if o1 * o2 < 0 && o3 * o4 < 0 {
+        return Some(Contact::Crossing);
+    }
+
+    // An endpoint lying strictly inside the other segment
+    let inside = |s: Vec2, e: Vec2, x: Vec2| {
+        let t = (x - s).dot(e - s) / (e - s).length_squared();
+        t > eps / scale && t < 1.0 - eps / scale
+    };
+    if (o1 == 0 && inside(p, p2, q))
+        || (o2 == 0 && inside(p, p2, q2))
+        || (o3 == 0 && inside(q, q2, p))
+        || (o4 == 0 && inside(q, q2, p2))
+    {
+        return Some(Contact::Touch);
+    }
+    None
+}
+
+/// Pairs of segment indices whose bounding boxes share a cell of a uniform grid
+fn candidate_pairs(segments: &[Segment], cell: f32) -> HashSet<(usize, usize)> {
+    let key = |p: Vec2| ((p.x / cell).floor() as i32, (p.y / cell).floor() as i32);
+    let mut buckets: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (n, s) in segments.iter().enumerate() {
+        let lo = key(s.start().min(s.end()));
+        let hi = key(s.start().max(s.end()));
+        for x in lo.0..=hi.0 {
+            for y in lo.1..=hi.1 {
+                buckets.entry((x, y)).or_default().push(n);
+            }
+        }
+    }
+    let mut pairs = HashSet::new();
+    for bucket in buckets.values() {
+        for (i, a) in bucket.iter().enumerate() {
+            for b in bucket[i + 1..].iter() {
+                pairs.insert((*a.min(b), *a.max(b)));
+            }
+        }
+    }
+    pairs
+}
+
+/// Check the segments, taken in drawing order, as a path on the lattice
+pub fn analyze(segments: &[Segment], lattice: Lattice) -> CurveReport {
+    let tolerance = lattice.tolerance();
+
+    let gaps = segments
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w[0].end().distance(w[1].start()) > tolerance)
+        .map(|(n, _)| n + 1)
+        .collect();
+
+    let mean_length =
+        segments.iter().map(|s| s.length()).sum::<f32>() / segments.len().max(1) as f32;
+    let mut contacts = candidate_pairs(segments, mean_length.max(tolerance) * 2.0)
+        .into_iter()
+        .filter_map(|(i, j)| Some((i, j, contact(&segments[i], &segments[j], tolerance)?)))
+        .collect::<Vec<_>>();
+    contacts.sort_by_key(|c| (c.0, c.1));
+
+    let vertices = segments
+        .first()
+        .map(|s| s.start())
+        .into_iter()
+        .chain(segments.iter().map(|s| s.end()))
+        .collect::<Vec<Vec2>>();
+    let origin = vertices.first().copied().unwrap_or(Vec2::ZERO);
+
+    let mut visits: HashMap<(i32, i32), usize> = HashMap::new();
+    let mut off_lattice = Vec::new();
+    for v in vertices.iter() {
+        let c = lattice.coords(*v - origin);
+        if lattice.point(c).distance(*v - origin) > tolerance {
+            off_lattice.push(*v);
+        }
+        *visits.entry(c).or_default() += 1;
+    }
+    let mut revisited = visits
+        .iter()
+        .filter(|(_, n)| **n > 1)
+        .map(|(c, _)| lattice.point(*c) + origin)
+        .collect::<Vec<Vec2>>();
+    revisited.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+
+    let coverage = {
+        let (mut lo, mut hi) = ((i32::MAX, i32::MAX), (i32::MIN, i32::MIN));
+        for c in visits.keys() {
+            lo = (lo.0.min(c.0), lo.1.min(c.1));
+            hi = (hi.0.max(c.0), hi.1.max(c.1));
+        }
+        let total = if visits.is_empty() {
+            0
+        } else {
+            ((hi.0 - lo.0 + 1) * (hi.1 - lo.1 + 1)) as usize
+        };
+        Coverage {
+            visited: visits.len(),
+            total,
+        }
+    };
+
+    CurveReport {
+        segments: segments.len(),
+        gaps,
+        contacts,
+        revisited,
+        off_lattice,
+        coverage,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::prelude::Vec2;
+
+    use super::{analyze, Lattice};
+    use crate::l_system::{self, SymbolReader};
+
+    fn segments(mut reader: SymbolReader) -> Vec<crate::segment::Segment> {
+        reader.read_all();
+        reader.segments
+    }
+
+    #[test]
+    fn hilbert_fills_square() {
+        let report = analyze(
+            &segments(l_system::hilbert::reader()),
+            Lattice::square(12.0),
+        );
+        assert!(report.is_space_filling(), "{:?}", report.coverage);
+    }
+
+    #[test]
+    fn peano_fills_square() {
+        let report = analyze(&segments(l_system::peano::reader()), Lattice::square(15.0));
+        assert!(report.is_space_filling(), "{:?}", report.coverage);
+    }
+
+    #[test]
+    fn peano_variety_fills_square() {
+        let report = analyze(
+            &segments(l_system::peano_variety::reader()),
+            Lattice::square(15.0),
+        );
+        assert!(report.is_space_filling(), "{:?}", report.coverage);
+    }
+
+    #[test]
+    fn peano_gosper_is_simple() {
+        let report = analyze(
+            &segments(l_system::peano_gosper::reader()),
+            Lattice::triangular(Vec2::new(0.0, 10.0)),
+        );
+        assert!(report.is_simple_path());
+        assert!(report.off_lattice.is_empty());
+    }
+
+    #[test]
+    fn tree_is_not_simple() {
+        let report = analyze(&segments(l_system::tree::reader()), Lattice::square(60.0));
+        assert!(!report.is_simple_path());
+    }
+}
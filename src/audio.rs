@@ -0,0 +1,348 @@
+use std::{
+    f32::consts::PI,
+    path::{Path, PathBuf},
+};
+
+use crate::tween::Easing;
+
+/// Sound loaded from a WAV file, mixed down to one channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audio {
+    pub sample_rate: u32,
+    /// From -1 to 1
+    pub samples: Vec<f32>,
+}
+
+impl Audio {
+    pub fn load(path: impl AsRef<Path>) -> Result<Audio, String> {
+        let path = path.as_ref();
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Audio::parse(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Read a WAV file of 8, 16, 24 or 32 bit integer or 32 bit float samples
+    pub fn parse(bytes: &[u8]) -> Result<Audio, String> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(String::from("not a WAV file"));
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+        // Format tag, channels, sample rate and bits per sample
+        let mut format = None;
+        let mut data = None;
+        let mut i = 12;
+        while i + 8 <= bytes.len() {
+            let size = u32_at(i + 4) as usize;
+            let body = i + 8..(i + 8 + size).min(bytes.len());
+            match &bytes[i..i + 4] {
+                b"fmt " if body.len() >= 16 => {
+                    let mut tag = u16_at(body.start);
+                    // Extensible files keep the real tag at the start of the subformat
+                    if tag == 0xFFFE && body.len() >= 26 {
+                        tag = u16_at(body.start + 24);
+                    }
+                    format = Some((
+                        tag,
+                        u16_at(body.start + 2),
+                        u32_at(body.start + 4),
+                        u16_at(body.start + 14),
+                    ));
+                }
+                b"data" => data = Some(&bytes[body]),
+                _ => (),
+            }
+            // Chunks are padded to an even length
+            i += 8 + size + size % 2;
+        }
+        let (tag, channels, sample_rate, bits) = format.ok_or("no fmt chunk")?;
+        let data = data.ok_or("no data chunk")?;
+        if channels == 0 || sample_rate == 0 {
+            return Err(String::from("no channels"));
+        }
+
+        let width = bits as usize / 8;
+        let sample = |b: &[u8]| -> f32 {
+            match (tag, bits) {
+                (1, 8) => (b[0] as f32 - 128.0) / 128.0,
+                (1, 16) => i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+                (1, 24) => (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8388608.0,
+                (1, 32) => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
+                _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+            }
+        };
+        match (tag, bits) {
+            (1, 8 | 16 | 24 | 32) | (3, 32) => (),
+            _ => return Err(format!("unsupported format {} with {} bits", tag, bits)),
+        }
+        let samples = data
+            .chunks_exact(width * channels as usize)
+            .map(|frame| frame.chunks_exact(width).map(sample).sum::<f32>() / channels as f32)
+            .collect();
+        Ok(Audio {
+            sample_rate,
+            samples,
+        })
+    }
+
+    /// Length in seconds
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+
+    /// Loudness and the strength of each frequency band for every frame at the frame rate.
+    /// The bands are spaced evenly in pitch from 40 Hz up to 16 kHz or the highest frequency in
+    /// the file. Loudness is scaled so its loudest frame is 1, and the bands together so the
+    /// strongest band at its strongest is 1.
+    pub fn signals(&self, fps: u32, bands: usize) -> Signals {
+        let fps = fps.max(1);
+        let hop = self.sample_rate as f32 / fps as f32;
+        let frames = (self.samples.len() as f32 / hop).ceil() as usize;
+        // Long enough to cover a whole frame, and a power of two for the FFT
+        let window = (hop.ceil() as usize).next_power_of_two().max(64);
+        let hann = (0..window)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / window as f32).cos())
+            .collect::<Vec<f32>>();
+
+        // Bin edges of the bands, each band at least one bin wide
+        let bin_hz = self.sample_rate as f32 / window as f32;
+        let top = (self.sample_rate as f32 / 2.0).min(16000.0);
+        let edges = (0..=bands)
+            .map(|b| {
+                let hz = 40.0 * (top / 40.0).powf(b as f32 / bands.max(1) as f32);
+                ((hz / bin_hz) as usize).clamp(1, window / 2)
+            })
+            .collect::<Vec<usize>>();
+
+        let mut amplitude = Vec::with_capacity(frames);
+        let mut band_values = vec![Vec::with_capacity(frames); bands];
+        let (mut re, mut im) = (vec![0.0; window], vec![0.0; window]);
+        for frame in 0..frames {
+            let start = (frame as f32 * hop) as usize;
+            let end = ((frame + 1) as f32 * hop) as usize;
+            let own = &self.samples[start.min(self.samples.len())..end.min(self.samples.len())];
+            let rms = (own.iter().map(|s| s * s).sum::<f32>() / own.len().max(1) as f32).sqrt();
+            amplitude.push(rms);
+
+            for i in 0..window {
+                re[i] = self.samples.get(start + i).copied().unwrap_or(0.0) * hann[i];
+                im[i] = 0.0;
+            }
+            fft(&mut re, &mut im);
+            for (b, values) in band_values.iter_mut().enumerate() {
+                let (low, high) = (edges[b], edges[b + 1].max(edges[b] + 1));
+                let bins = low..high.min(window / 2 + 1);
+                let count = bins.len().max(1);
+                // Root mean square, so a tone smeared across bins keeps its strength
+                let power = bins.map(|k| re[k] * re[k] + im[k] * im[k]).sum::<f32>();
+                values.push((power / count as f32).sqrt());
+            }
+        }
+
+        normalize(&mut amplitude);
+        let peak = band_values.iter().flatten().fold(0.0f32, |a, b| a.max(*b));
+        for values in band_values.iter_mut() {
+            scale(values, peak);
+        }
+        Signals {
+            fps,
+            amplitude,
+            bands: band_values,
+            path: None,
+        }
+    }
+}
+
+fn normalize(values: &mut [f32]) {
+    let peak = values.iter().fold(0.0f32, |a, b| a.max(*b));
+    scale(values, peak);
+}
+
+fn scale(values: &mut [f32], peak: f32) {
+    if peak > 0.0 {
+        values.iter_mut().for_each(|v| *v /= peak);
+    }
+}
+
+/// In place radix 2 FFT of the complex values with the given real and imaginary parts. The
+/// length must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    // Put the values in bit reversed order
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let (br, bi) = (re[b] * cos - im[b] * sin, re[b] * sin + im[b] * cos);
+                re[b] = re[a] - br;
+                im[b] = im[a] - bi;
+                re[a] += br;
+                im[a] += bi;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Values from 0 to 1 for each frame of a soundtrack, to drive parameters of a sketch
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signals {
+    pub fps: u32,
+    /// Loudness of each frame
+    pub amplitude: Vec<f32>,
+    /// Strength of each band for each frame, lowest band first
+    pub bands: Vec<Vec<f32>>,
+    /// The file the signals came from, to add to recordings
+    pub path: Option<PathBuf>,
+}
+
+impl Signals {
+    /// Signals of the WAV file at the frame rate
+    pub fn load(path: impl AsRef<Path>, fps: u32, bands: usize) -> Result<Signals, String> {
+        let path = path.as_ref();
+        let mut signals = Audio::load(path)?.signals(fps, bands);
+        signals.path = Some(path.to_path_buf());
+        Ok(signals)
+    }
+
+    /// Signals of the WAV file named by the AUDIO environment variable, if it is set
+    pub fn from_env(fps: u32, bands: usize) -> Option<Signals> {
+        let path = std::env::var_os("AUDIO")?;
+        match Signals::load(&path, fps, bands) {
+            Ok(signals) => Some(signals),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// Number of frames the soundtrack lasts
+    pub fn frames(&self) -> u64 {
+        self.amplitude.len() as u64
+    }
+
+    /// The value of the source at the frame, 0 once the soundtrack has ended
+    pub fn get(&self, source: Source, frame: u64) -> f32 {
+        let values = match source {
+            Source::Amplitude => &self.amplitude,
+            Source::Band(b) => match self.bands.get(b) {
+                Some(values) => values,
+                None => return 0.0,
+            },
+        };
+        values.get(frame as usize).copied().unwrap_or(0.0)
+    }
+}
+
+/// Which of the signals to follow
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Source {
+    Amplitude,
+    Band(usize),
+}
+
+/// A parameter driven by a signal, moving from low when it is silent to high when it peaks
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Binding {
+    pub source: Source,
+    pub low: f32,
+    pub high: f32,
+    /// Shapes the response, an easing in makes quiet passages quieter
+    pub easing: Easing,
+}
+
+impl Binding {
+    pub fn new(source: Source, low: f32, high: f32) -> Self {
+        Binding {
+            source,
+            low,
+            high,
+            easing: Easing::Linear,
+        }
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn value(&self, signals: &Signals, frame: u64) -> f32 {
+        let t = self.easing.ease(signals.get(self.source, frame));
+        self.low + (self.high - self.low) * t
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let data = samples.len() as u32 * 2;
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data.to_le_bytes());
+        for s in samples {
+            bytes.extend_from_slice(&s.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn tone_is_found_in_its_band() {
+        // Half a second of quiet then half a second of a loud 1 kHz tone
+        let rate = 8000;
+        let samples = (0..rate)
+            .map(|i| {
+                let loudness = if i < rate / 2 { 0.01 } else { 0.8 };
+                let t = i as f32 / rate as f32;
+                ((2.0 * PI * 1000.0 * t).sin() * loudness * 32767.0) as i16
+            })
+            .collect::<Vec<i16>>();
+        let audio = Audio::parse(&wav(&samples, rate)).unwrap();
+        assert_eq!(audio.samples.len(), rate as usize);
+        assert_eq!(audio.duration(), 1.0);
+
+        let signals = audio.signals(10, 4);
+        assert_eq!(signals.frames(), 10);
+        assert!(signals.get(Source::Amplitude, 2) < 0.05);
+        assert!(signals.get(Source::Amplitude, 7) > 0.95);
+        // 40 Hz to 4 kHz in four bands puts 1 kHz in the third
+        let loud = (0..4)
+            .map(|b| signals.get(Source::Band(b), 7))
+            .collect::<Vec<f32>>();
+        assert!(loud[2] > 0.9, "{:?}", loud);
+        assert!([0, 1, 3].iter().all(|b| loud[*b] < 0.5), "{:?}", loud);
+        assert_eq!(signals.get(Source::Amplitude, 100), 0.0);
+        let binding = Binding::new(Source::Amplitude, 10.0, 20.0);
+        assert!((binding.value(&signals, 7) - 20.0).abs() < 0.5);
+    }
+}
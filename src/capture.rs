@@ -1,15 +1,228 @@
+use std::{
+    cell::{Cell, RefCell},
+    path::{Path, PathBuf},
+};
+
 use nannou::prelude::*;
 
-// To create am mp4 from the images use the command below from the directory they are saved to
-// ffmpeg -r 30 -f image2 -s 1920x1080 -i %04d.png -vcodec libx264 -crf 25  -pix_fmt yuv420p INSERT_NAME.mp4
-pub fn captured_frame_path(app: &App, frame: &Frame, dir_name: &str) -> std::path::PathBuf {
-    // Create a path that we want to save this frame to.
-    app.project_path()
-        .expect("failed to locate `project_path`")
-        // Capture all frames to a directory called `/<path_to_nannou>/nannou/simple_capture`.
-        .join(dir_name)
-        // Name each file after the number of the frame.
-        .join(format!("{:04}", frame.nth()))
-        // The extension will be PNG. We also support tiff, bmp, gif, jpeg, webp and some others.
-        .with_extension("png")
+use crate::{
+    encode::{self, Encoding, Format},
+    run::{sidecar_path, Run},
+};
+
+/// Records the frames of a window to numbered PNGs while switched on.
+///
+/// Call `capture` at the end of a view, after drawing to the frame. Pressing the key starts and
+/// stops recording. Each recording gets a new numbered folder inside the session's folder, with
+/// its frames numbered from 0. The state lives
+/// in cells so sessions can be kept in a model that views only see by reference.
+pub struct Session {
+    /// Folder the recordings are written to, relative paths are taken from the project directory
+    pub dir: PathBuf,
+    pub key: Key,
+    recording: Cell<bool>,
+    key_was_down: Cell<bool>,
+    frames: Cell<usize>,
+    /// Animations to make from the frames when recording stops
+    pub encodings: Vec<(Format, Encoding)>,
+    /// Written beside the frames and animations of each recording
+    pub run: Option<Run>,
+    // The run of the current recording with its window size and time filled in
+    stamped: RefCell<Option<Run>>,
+    // Folder of the current recording, resolved and created when it starts
+    active_dir: RefCell<Option<PathBuf>>,
+}
+
+impl Session {
+    /// A session that is not recording, toggled with R
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Session {
+            dir: dir.into(),
+            key: Key::R,
+            recording: Cell::new(false),
+            key_was_down: Cell::new(false),
+            frames: Cell::new(0),
+            encodings: Vec::new(),
+            run: None,
+            stamped: RefCell::new(None),
+            active_dir: RefCell::new(None),
+        }
+    }
+
+    pub fn key(mut self, key: Key) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Also encode the frames to an animation when recording stops
+    pub fn encode(mut self, format: Format, encoding: Encoding) -> Self {
+        self.encodings.push((format, encoding));
+        self
+    }
+
+    pub fn run(mut self, run: Run) -> Self {
+        self.run = Some(run);
+        self
+    }
+
+    /// Start recording with the first frame rather than waiting for the key
+    pub fn recording(self) -> Self {
+        self.recording.set(true);
+        self
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.get()
+    }
+
+    /// Frames captured since recording last started
+    pub fn frames(&self) -> usize {
+        self.frames.get()
+    }
+
+    /// Create a folder for a new recording, numbered one after the highest already in the
+    /// session's folder so no earlier recording is written over
+    fn open(&self, app: &App) -> Result<PathBuf, String> {
+        let root = if self.dir.is_absolute() {
+            self.dir.clone()
+        } else {
+            app.project_path()
+                .map_err(|e| format!("failed to locate the project path: {}", e))?
+                .join(&self.dir)
+        };
+        std::fs::create_dir_all(&root)
+            .map_err(|e| format!("failed to create {}: {}", root.display(), e))?;
+        let next = std::fs::read_dir(&root)
+            .map_err(|e| format!("failed to read {}: {}", root.display(), e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .filter_map(|path| path.file_name()?.to_str()?.parse::<usize>().ok())
+            .max()
+            .map_or(0, |n| n + 1);
+        let dir = root.join(format!("{:03}", next));
+        std::fs::create_dir(&dir)
+            .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        Ok(dir)
+    }
+
+    /// Start a new recording. The frame numbering restarts at 0.
+    pub fn start(&self, app: &App) -> Result<(), String> {
+        let dir = self.open(app)?;
+        println!("recording to {}", dir.display());
+        if let Some(run) = &self.run {
+            let (w, h) = app.main_window().inner_size_pixels();
+            let run = run.clone().window(w, h).stamp();
+            self.write_sidecar(&run, &dir);
+            *self.stamped.borrow_mut() = Some(run);
+        }
+        *self.active_dir.borrow_mut() = Some(dir);
+        self.frames.set(0);
+        self.recording.set(true);
+        Ok(())
+    }
+
+    /// Stop recording, then wait for the frames to be written and report any that were not
+    pub fn stop(&self, app: &App) {
+        if self.recording.replace(false) {
+            if let Err(e) = app.main_window().await_capture_frame_jobs() {
+                eprintln!("gave up waiting for frames to be written: {:?}", e);
+            }
+            let missing = (0..self.frames.get())
+                .filter_map(|i| self.frame_path(i))
+                .filter(|path| !path.exists())
+                .count();
+            if missing > 0 {
+                eprintln!(
+                    "failed to write {} of {} frames",
+                    missing,
+                    self.frames.get()
+                );
+            } else {
+                println!("recorded {} frames", self.frames.get());
+                self.encode_frames();
+            }
+        }
+        *self.active_dir.borrow_mut() = None;
+    }
+
+    /// Make each animation, then remove the frames if any encoding asks and all succeeded
+    fn encode_frames(&self) {
+        let dir = match self.active_dir.borrow().clone() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let frames = (0..self.frames.get())
+            .filter_map(|i| self.frame_path(i))
+            .collect::<Vec<PathBuf>>();
+        let mut ok = true;
+        for (format, encoding) in self.encodings.iter() {
+            let output = encode::beside(&dir, format.extension());
+            let result = match format {
+                Format::Gif => encode::gif(&frames, &output, encoding),
+                Format::Mp4 => encode::mp4(&frames, &output, encoding),
+            };
+            match result {
+                Ok(()) => {
+                    println!("encoded {}", output.display());
+                    if let Some(run) = self.stamped.borrow().as_ref() {
+                        self.write_sidecar(run, &output);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    ok = false;
+                }
+            }
+        }
+        if ok && self.encodings.iter().any(|(_, e)| e.cleanup) {
+            if let Err(e) = encode::remove_frames(&frames) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+
+    /// Path of the nth frame of the current recording
+    fn frame_path(&self, n: usize) -> Option<PathBuf> {
+        self.active_dir
+            .borrow()
+            .as_ref()
+            .map(|dir| dir.join(format!("{:04}", n)).with_extension("png"))
+    }
+
+    /// Handle the toggle key and capture the frame just drawn if recording.
+    /// Failures are reported and stop the recording rather than ending the app.
+    pub fn capture(&self, app: &App) {
+        let down = app.keys.down.contains(&self.key);
+        if down && !self.key_was_down.get() {
+            if self.is_recording() {
+                self.stop(app);
+            } else if let Err(e) = self.start(app) {
+                eprintln!("{}", e);
+            }
+        }
+        self.key_was_down.set(down);
+
+        if !self.is_recording() {
+            return;
+        }
+        // Recording from the start has no folder until the first frame
+        if self.active_dir.borrow().is_none() {
+            if let Err(e) = self.start(app) {
+                eprintln!("{}", e);
+                self.recording.set(false);
+                return;
+            }
+        }
+        if let Some(path) = self.frame_path(self.frames.get()) {
+            app.main_window().capture_frame(path);
+            self.frames.set(self.frames.get() + 1);
+        }
+    }
+
+    fn write_sidecar(&self, run: &Run, path: &Path) {
+        let sidecar = sidecar_path(path);
+        if let Err(e) = run.save(&sidecar) {
+            eprintln!("failed to write {}: {}", sidecar.display(), e);
+        }
+    }
 }
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use nannou::prelude::Update;
+
+/// Time as a count of fixed length frames, so updates and views see exact, evenly spaced times.
+///
+/// Live clocks follow real time, jumping several frames at once if rendering falls behind.
+/// Offline clocks advance exactly one frame per update however long it takes, so every frame
+/// is drawn and a capture plays back at the right speed whatever the machine.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Clock {
+    pub fps: u32,
+    pub offline: bool,
+    frame: u64,
+    // Frames advanced by the last tick
+    steps: u64,
+    // Real time of the first tick, live frames count from here
+    start: Option<Duration>,
+}
+
+impl Clock {
+    /// A live clock
+    pub fn new(fps: u32) -> Self {
+        Clock {
+            fps: fps.max(1),
+            offline: false,
+            frame: 0,
+            steps: 0,
+            start: None,
+        }
+    }
+
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    /// Offline if the RENDER_OFFLINE environment variable is set, otherwise live
+    pub fn from_env(fps: u32) -> Self {
+        let clock = Clock::new(fps);
+        if std::env::var_os("RENDER_OFFLINE").is_some() {
+            clock.offline()
+        } else {
+            clock
+        }
+    }
+
+    /// Advance the clock, call once at the start of each update
+    pub fn tick(&mut self, update: &Update) {
+        let next = if self.offline {
+            // The first tick shows frame 0
+            if self.start.is_none() {
+                self.start = Some(update.since_start);
+                0
+            } else {
+                self.frame + 1
+            }
+        } else {
+            let start = *self.start.get_or_insert(update.since_start);
+            ((update.since_start - start).as_secs_f64() * self.fps as f64).floor() as u64
+        };
+        self.steps = next - self.frame;
+        self.frame = next;
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Frames advanced by the last tick. Sketches that move in fixed steps should take this
+    /// many so they keep to time.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Seconds since the first frame
+    pub fn time(&self) -> f32 {
+        (self.frame as f64 / self.fps as f64) as f32
+    }
+
+    /// Seconds per frame
+    pub fn dt(&self) -> f32 {
+        1.0 / self.fps as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Updates at the given real times in milliseconds
+    fn run(mut clock: Clock, times: &[u64]) -> Vec<(u64, u64)> {
+        let mut last = Duration::ZERO;
+        times
+            .iter()
+            .map(|ms| {
+                let since_start = Duration::from_millis(*ms);
+                clock.tick(&Update {
+                    since_last: since_start - last,
+                    since_start,
+                });
+                last = since_start;
+                (clock.frame(), clock.steps())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn offline_ticks_one_frame_whatever_the_time() {
+        // Updates close together, then far apart as a slow capture would make them
+        let ticks = run(Clock::new(30).offline(), &[5, 6, 7, 2000, 2001, 60000]);
+        assert_eq!(ticks, [(0, 0), (1, 1), (2, 1), (3, 1), (4, 1), (5, 1)]);
+        let mut clock = Clock::new(30).offline();
+        for frame in 0..90 {
+            clock.tick(&Update {
+                since_last: Duration::from_secs(1),
+                since_start: Duration::from_secs(frame),
+            });
+        }
+        assert_eq!(clock.frame(), 89);
+        assert_eq!(clock.time(), 89.0 / 30.0);
+        assert_eq!(clock.dt(), 1.0 / 30.0);
+    }
+
+    #[test]
+    fn live_steps_follow_real_time() {
+        // At 10 fps: no time yet, a frame, not quite another, then three frames at once
+        let ticks = run(Clock::new(10), &[500, 600, 650, 950]);
+        assert_eq!(ticks, [(0, 0), (1, 1), (1, 0), (4, 3)]);
+    }
+}
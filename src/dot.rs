@@ -1,38 +1,100 @@
 use nannou::prelude::*;
 
+use crate::{
+    audio::{Binding, Signals, Source},
+    capture::Session,
+    clock::Clock,
+    encode::{Encoding, Format},
+    run::{Params, Run},
+    tween::{Easing, Track},
+};
+
+const FPS: u32 = 60;
+
 pub struct Model {
+    clock: Clock,
+    capture: Session,
     x: f32,
     y: f32,
-    radius: f32,
-    radius_step: f32,
+    radius: Track<f32>,
+    /// Soundtrack the radius follows instead, from the WAV file named by AUDIO
+    audio: Option<(Signals, Binding)>,
 }
 
 pub fn model(_app: &App) -> Model {
+    with_audio(Signals::from_env(FPS, 8))
+}
+
+/// The dot following the soundtrack of an earlier run, or growing and shrinking if it had none
+pub fn replay(run: &Run) -> Result<Model, String> {
+    match &run.params {
+        Params::Dot { audio: Some(path) } => Ok(with_audio(Some(Signals::load(path, FPS, 8)?))),
+        Params::Dot { audio: None } => Ok(with_audio(None)),
+        _ => Err(format!("{} is not a dot run", run.sketch)),
+    }
+}
+
+fn with_audio(signals: Option<Signals>) -> Model {
+    let audio = signals.map(|signals| {
+        // Loudness pushed towards the extremes so beats stand out
+        let binding = Binding::new(Source::Amplitude, 10.0, 300.0).easing(Easing::QuadIn);
+        (signals, binding)
+    });
+    let path = audio.as_ref().and_then(|(s, _)| s.path.clone());
+    let mut encoding = Encoding::default().fps(FPS);
+    if let Some(path) = &path {
+        encoding = encoding.audio(path);
+    }
+    let capture = Session::new("dot")
+        .run(Run::new("dot", Params::Dot { audio: path }))
+        .encode(Format::Mp4, encoding);
     Model {
+        // Recording the soundtrack steps a frame at a time however long each takes to draw, so
+        // the frames stay in time with the sound
+        clock: if audio.is_some() {
+            Clock::new(FPS).offline()
+        } else {
+            Clock::from_env(FPS)
+        },
+        // With a soundtrack the whole of it is recorded, starting with the first frame so the
+        // sound lines up
+        capture: if audio.is_some() {
+            capture.recording()
+        } else {
+            capture
+        },
         x: 0.0,
         y: 0.0,
-        radius: 10.0,
-        radius_step: 1.0,
+        // Grows and shrinks back forever, taking as long as the old step of one per frame
+        radius: Track::new(10.0)
+            .to(290.0 / 60.0, 300.0, Easing::CubicInOut)
+            .yoyo(),
+        audio,
     }
 }
 
-pub fn update(_app: &App, model: &mut Model, _update: Update) {
-    if model.radius >= 300.0 || model.radius <= 10.0 {
-        model.radius_step *= -1.0;
-    }
-
-    model.radius += model.radius_step;
-    model.radius = model.radius.clamp(10.0, 300.0);
+pub fn update(_app: &App, model: &mut Model, update: Update) {
+    model.clock.tick(&update);
 }
 
 pub fn view(app: &App, model: &Model, frame: Frame) {
+    let radius = match &model.audio {
+        Some((signals, _)) if model.clock.frame() >= signals.frames() => {
+            model.capture.stop(app);
+            app.quit();
+            return;
+        }
+        Some((signals, binding)) => binding.value(signals, model.clock.frame()),
+        None => model.radius.sample(model.clock.time()),
+    };
+
     let draw = app.draw();
     draw.background().color(HONEYDEW);
     draw.ellipse()
         .color(STEELBLUE)
-        .w(model.radius)
-        .h(model.radius)
+        .w(radius)
+        .h(radius)
         .x_y(model.x, model.y);
     draw.to_frame(app, &frame).unwrap();
-    
+    model.capture.capture(app);
 }
@@ -0,0 +1,297 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use nannou::image::{
+    self,
+    gif::{GifEncoder, Repeat},
+    imageops::FilterType,
+    Delay, Frame as GifFrame, RgbaImage,
+};
+
+/// Animation file formats frames can be encoded to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// Encoded in process, each frame gets its own palette
+    Gif,
+    /// H.264 encoded by the ffmpeg found on the PATH
+    Mp4,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Gif => "gif",
+            Format::Mp4 => "mp4",
+        }
+    }
+}
+
+/// Settings for turning a folder of numbered frames into an animation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Encoding {
+    pub fps: u32,
+    /// Quality for MP4, lower is better. 0 is lossless and 51 the worst.
+    pub crf: u8,
+    /// Width and height to scale to, None to keep the size of the frames
+    pub size: Option<(u32, u32)>,
+    /// Delete the frames once the animation is written
+    pub cleanup: bool,
+    /// Soundtrack for MP4s, played from its start with the first frame. GIFs have no sound.
+    pub audio: Option<PathBuf>,
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding {
+            fps: 30,
+            crf: 25,
+            size: None,
+            cleanup: false,
+            audio: None,
+        }
+    }
+}
+
+impl Encoding {
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    pub fn crf(mut self, crf: u8) -> Self {
+        self.crf = crf;
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    pub fn cleanup(mut self) -> Self {
+        self.cleanup = true;
+        self
+    }
+
+    pub fn audio(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audio = Some(path.into());
+        self
+    }
+}
+
+/// The number of a frame from its file name, None unless the file is a PNG named with digits
+fn frame_number(path: &Path) -> Option<u64> {
+    let stem = path.file_stem()?.to_str()?;
+    // Parsing alone would take a sign as well
+    if path.extension()? != "png" || !stem.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    stem.parse().ok()
+}
+
+/// The numbered PNGs in the folder, in order. They're sorted by number rather than by name so
+/// frames past the width of the padding, like 10000 after 9999, stay in place.
+pub fn frames(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, String> {
+    let dir = dir.as_ref();
+    let mut frames = std::fs::read_dir(dir)
+        .map_err(|e| format!("failed to read {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| Some((frame_number(&path)?, path)))
+        .collect::<Vec<(u64, PathBuf)>>();
+    frames.sort();
+    if frames.is_empty() {
+        return Err(format!("no frames in {}", dir.display()));
+    }
+    Ok(frames.into_iter().map(|(_, path)| path).collect())
+}
+
+fn load(path: &Path, size: Option<(u32, u32)>) -> Result<RgbaImage, String> {
+    let image = image::open(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?
+        .to_rgba8();
+    Ok(match size {
+        Some((w, h)) if (w, h) != image.dimensions() => {
+            image::imageops::resize(&image, w, h, FilterType::Triangle)
+        }
+        _ => image,
+    })
+}
+
+/// Encode the frames to an animated GIF, looping forever
+pub fn gif(
+    frames: &[PathBuf],
+    output: impl AsRef<Path>,
+    encoding: &Encoding,
+) -> Result<(), String> {
+    let output = output.as_ref();
+    let file = std::fs::File::create(output)
+        .map_err(|e| format!("failed to create {}: {}", output.display(), e))?;
+    let mut encoder = GifEncoder::new(file);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .map_err(|e| format!("failed to loop {}: {}", output.display(), e))?;
+    let delay = Delay::from_numer_denom_ms(1000, encoding.fps.max(1));
+    for path in frames {
+        let frame = GifFrame::from_parts(load(path, encoding.size)?, 0, 0, delay);
+        encoder
+            .encode_frame(frame)
+            .map_err(|e| format!("failed to encode {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Encode the frames to an MP4 by piping them to ffmpeg, with the soundtrack if there is one.
+/// The video is cut to the shorter of the frames and the soundtrack.
+pub fn mp4(
+    frames: &[PathBuf],
+    output: impl AsRef<Path>,
+    encoding: &Encoding,
+) -> Result<(), String> {
+    let output = output.as_ref();
+    let mut command = Command::new("ffmpeg");
+    command
+        .args(["-y", "-loglevel", "error", "-f", "image2pipe", "-framerate"])
+        .arg(encoding.fps.to_string())
+        .args(["-i", "-"]);
+    if let Some(audio) = &encoding.audio {
+        command.arg("-i").arg(audio).args([
+            "-map",
+            "0:v",
+            "-map",
+            "1:a",
+            "-acodec",
+            "aac",
+            "-shortest",
+        ]);
+    }
+    command
+        .args(["-vcodec", "libx264", "-crf"])
+        .arg(encoding.crf.to_string());
+    // libx264 needs even dimensions for yuv420p
+    let scale = match encoding.size {
+        Some((w, h)) => format!("scale={}:{}", w - w % 2, h - h % 2),
+        None => String::from("scale=trunc(iw/2)*2:trunc(ih/2)*2"),
+    };
+    command
+        .args(["-vf", &scale, "-pix_fmt", "yuv420p"])
+        .arg(output)
+        .stdin(Stdio::piped());
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("failed to run ffmpeg, is it installed? {}", e))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    for path in frames {
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        if let Err(e) = stdin.write_all(&bytes) {
+            // ffmpeg has quit, its exit status says why
+            eprintln!("failed to send {} to ffmpeg: {}", path.display(), e);
+            break;
+        }
+    }
+    drop(stdin);
+    let status = child
+        .wait()
+        .map_err(|e| format!("failed to wait for ffmpeg: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("ffmpeg failed with {}", status))
+    }
+}
+
+/// The file beside the folder named after it with the extension added, keeping any dots already
+/// in the folder's name
+pub fn beside(dir: impl AsRef<Path>, extension: &str) -> PathBuf {
+    let dir = dir.as_ref();
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    dir.with_file_name(name)
+}
+
+/// Encode the frames in the folder to a file beside it named after the folder, deleting the
+/// frames afterwards if the encoding asks. Returns the path of the animation.
+pub fn encode(
+    dir: impl AsRef<Path>,
+    format: Format,
+    encoding: &Encoding,
+) -> Result<PathBuf, String> {
+    let dir = dir.as_ref();
+    let frames = frames(dir)?;
+    let output = beside(dir, format.extension());
+    match format {
+        Format::Gif => gif(&frames, &output, encoding)?,
+        Format::Mp4 => mp4(&frames, &output, encoding)?,
+    }
+    if encoding.cleanup {
+        remove_frames(&frames)?;
+    }
+    Ok(output)
+}
+
+pub fn remove_frames(frames: &[PathBuf]) -> Result<(), String> {
+    for path in frames {
+        std::fs::remove_file(path)
+            .map_err(|e| format!("failed to remove {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_in_number_order() {
+        let dir = std::env::temp_dir().join(format!("art_frames.{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in [
+            "10000.png",
+            "9999.png",
+            "0002.png",
+            "notes.txt",
+            "cover.png",
+            "12.jpg",
+        ] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let names = frames(&dir)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, ["0002.png", "9999.png", "10000.png"]);
+    }
+
+    #[test]
+    fn gif_loops_forever() {
+        let dir = std::env::temp_dir().join(format!("art_gif.{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (i, shade) in [0, 255].iter().enumerate() {
+            RgbaImage::from_pixel(4, 4, image::Rgba([*shade, 0, 0, 255]))
+                .save(dir.join(format!("{:04}.png", i)))
+                .unwrap();
+        }
+        let output = dir.join("loop.gif");
+        gif(&frames(&dir).unwrap(), &output, &Encoding::default()).unwrap();
+        let bytes = std::fs::read(&output).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        // The application extension browsers and viewers read the loop count from
+        assert!(bytes.windows(11).any(|w| w == b"NETSCAPE2.0"));
+    }
+
+    #[test]
+    fn animations_beside_dotted_folders() {
+        assert_eq!(
+            beside("captures/v1.2", "mp4"),
+            PathBuf::from("captures/v1.2.mp4")
+        );
+        assert_eq!(beside("wave", "gif"), PathBuf::from("wave.gif"));
+    }
+}
@@ -0,0 +1,151 @@
+use nannou::{color::Rgba, prelude::Vec2, Draw};
+
+/// A circle turning at a fixed rate, carried on the end of the circle before it in a chain
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Epicycle {
+    /// Turns per turn of the first harmonic, negative to turn clockwise
+    pub frequency: f32,
+    pub radius: f32,
+    /// Angle at time 0 in radians
+    pub phase: f32,
+}
+
+impl Epicycle {
+    /// Offset from the center of the circle to its point at the given angle of the first harmonic
+    pub fn offset(&self, t: f32) -> Vec2 {
+        let angle = self.frequency * t + self.phase;
+        Vec2::new(angle.cos(), angle.sin()) * self.radius
+    }
+}
+
+/// The center of each circle in turn followed by the tip of the last, starting at the origin
+pub fn chain(epicycles: &[Epicycle], origin: Vec2, t: f32) -> Vec<Vec2> {
+    let mut points = Vec::with_capacity(epicycles.len() + 1);
+    points.push(origin);
+    let mut p = origin;
+    for e in epicycles {
+        p += e.offset(t);
+        points.push(p);
+    }
+    points
+}
+
+/// Where the tip of the chain is
+pub fn tip(epicycles: &[Epicycle], origin: Vec2, t: f32) -> Vec2 {
+    epicycles.iter().fold(origin, |p, e| p + e.offset(t))
+}
+
+/// Draw each circle and the arms joining their centers
+pub fn draw_chain(draw: &Draw, epicycles: &[Epicycle], origin: Vec2, t: f32, color: Rgba) {
+    let points = chain(epicycles, origin, t);
+    for (e, center) in epicycles.iter().zip(points.iter()) {
+        draw.ellipse()
+            .xy(*center)
+            .radius(e.radius.abs())
+            .no_fill()
+            .stroke_weight(1.0)
+            .stroke_color(color);
+    }
+    draw.polyline()
+        .weight(1.5)
+        .points_colored(points.into_iter().map(|p| (p, color)));
+}
+
+/// Points evenly spaced by distance around the closed path through the points, starting at the
+/// first point. The path returns from the last point to the first.
+pub fn resample_closed(points: &[Vec2], count: usize) -> Vec<Vec2> {
+    if points.len() < 2 || count == 0 {
+        return points.to_vec();
+    }
+    let edges = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+        .collect::<Vec<(Vec2, Vec2)>>();
+    let total = edges.iter().map(|(a, b)| a.distance(*b)).sum::<f32>();
+    if total <= 0.0 {
+        return vec![points[0]; count];
+    }
+    let step = total / count as f32;
+    let mut out = Vec::with_capacity(count);
+    let (mut edge, mut start) = (0, 0.0);
+    for i in 0..count {
+        let target = step * i as f32;
+        // Move on to the edge containing the target distance
+        while edge + 1 < edges.len() && start + edges[edge].0.distance(edges[edge].1) < target {
+            start += edges[edge].0.distance(edges[edge].1);
+            edge += 1;
+        }
+        let (a, b) = edges[edge];
+        let length = a.distance(b);
+        let t = if length > 0.0 {
+            (target - start) / length
+        } else {
+            0.0
+        };
+        out.push(a.lerp(b, t.clamp(0.0, 1.0)));
+    }
+    out
+}
+
+/// The epicycles whose tip passes through each point in turn as t goes from 0 to TAU, found
+/// with the discrete Fourier transform of the points as complex numbers. They are sorted by
+/// radius, largest first, so any prefix is the best approximation with that many circles.
+pub fn dft(points: &[Vec2]) -> Vec<Epicycle> {
+    let n = points.len();
+    // Frequencies centered on zero so the circles turn as slowly as possible
+    let low = -(n as i64 / 2);
+    let mut epicycles = (low..low + n as i64)
+        .map(|k| {
+            let mut sum = Vec2::ZERO;
+            for (j, p) in points.iter().enumerate() {
+                // Reduced mod n first to keep the angle small and exact
+                let turn = (k * j as i64).rem_euclid(n as i64);
+                let angle = -std::f32::consts::TAU * turn as f32 / n as f32;
+                let (sin, cos) = angle.sin_cos();
+                // Complex multiplication of the point by e^(i angle)
+                sum += Vec2::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos);
+            }
+            let coefficient = sum / n as f32;
+            Epicycle {
+                frequency: k as f32,
+                radius: coefficient.length(),
+                phase: coefficient.y.atan2(coefficient.x),
+            }
+        })
+        .collect::<Vec<Epicycle>>();
+    epicycles.sort_by(|a, b| b.radius.total_cmp(&a.radius));
+    epicycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dft_passes_through_the_points() {
+        let points = [
+            (0.0, 0.0),
+            (40.0, 10.0),
+            (50.0, 60.0),
+            (-20.0, 30.0),
+            (-10.0, 5.0),
+        ]
+        .map(Vec2::from);
+        let epicycles = dft(&points);
+        for (j, p) in points.iter().enumerate() {
+            let t = std::f32::consts::TAU * j as f32 / points.len() as f32;
+            assert!(tip(&epicycles, Vec2::ZERO, t).distance(*p) < 1e-3);
+        }
+        assert!(epicycles.windows(2).all(|w| w[0].radius >= w[1].radius));
+    }
+
+    #[test]
+    fn resampled_square_is_evenly_spaced() {
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)].map(Vec2::from);
+        let points = resample_closed(&square, 8);
+        assert_eq!(points.len(), 8);
+        assert!(points[1].distance(Vec2::new(5.0, 0.0)) < 1e-4);
+        assert!(points[5].distance(Vec2::new(5.0, 10.0)) < 1e-4);
+    }
+}
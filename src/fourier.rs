@@ -0,0 +1,191 @@
+use nannou::prelude::*;
+
+use crate::{
+    capture::Session,
+    clock::Clock,
+    encode::{Encoding, Format},
+    epicycle::{self, dft, resample_closed, Epicycle},
+    l_system::{self, SymbolReader},
+    run::{Params, Run},
+    segment::polylines,
+    svg,
+    timeline::Timeline,
+};
+
+// Number of evenly spaced points the path is resampled to, and so the most circles there can be
+const SAMPLES: usize = 512;
+// Size of the square the path is scaled to fit
+const SIZE: f32 = 700.0;
+// Points along the traced path, more than the samples so fast circles still look smooth
+const TRACE_POINTS: usize = 1024;
+
+/// A closed path redrawn by a chain of circles
+pub struct Model {
+    clock: Clock,
+    timeline: Timeline,
+    capture: Session,
+    /// Every circle of the transform, largest first
+    epicycles: Vec<Epicycle>,
+    /// How many of the largest circles are drawn
+    pub terms: usize,
+    /// The path being approximated
+    path: Vec<Vec2>,
+}
+
+impl Model {
+    /// Circles for the closed path through the points, which is centered and scaled to fit
+    pub fn from_points(points: &[Vec2]) -> Self {
+        Model::from_path(fit(&resample_closed(points, SAMPLES)))
+    }
+
+    /// Circles for a path already resampled and fitted, which is kept as it is
+    fn from_path(path: Vec<Vec2>) -> Self {
+        let epicycles = dft(&path);
+        let mut model = Model {
+            clock: Clock::from_env(30),
+            // Slow enough to follow the smallest circles
+            timeline: Timeline::new(20.0, 30),
+            capture: Session::new("fourier").encode(Format::Mp4, Encoding::default()),
+            terms: epicycles.len().min(32),
+            epicycles,
+            path,
+        };
+        model.capture.run = Some(model.run());
+        model
+    }
+
+    /// Circles for the longest subpath of SVG path data
+    pub fn from_svg_path(d: &str) -> Result<Self, String> {
+        let paths = svg::parse_path(d, 16)?;
+        let longest = paths
+            .iter()
+            .max_by_key(|p| p.len())
+            .ok_or("no points in path")?;
+        Ok(Model::from_points(longest))
+    }
+
+    /// Circles for the longest connected run drawn by the reader, joined end to start
+    pub fn from_reader(mut reader: SymbolReader) -> Self {
+        reader.read_all();
+        let paths = polylines(&reader.segments, 1e-3);
+        let longest = paths.iter().max_by_key(|p| p.len()).cloned();
+        Model::from_points(&longest.unwrap_or_default())
+    }
+
+    /// The path and the circles on screen, for the next recording
+    fn run(&self) -> Run {
+        Run::new(
+            "fourier",
+            Params::Fourier {
+                path: self.path.clone(),
+                terms: self.terms,
+            },
+        )
+    }
+
+    /// The largest circles, as many as are drawn
+    pub fn active(&self) -> &[Epicycle] {
+        &self.epicycles[..self.terms.min(self.epicycles.len())]
+    }
+}
+
+/// Center the points on the origin and scale them to fit in the square
+fn fit(points: &[Vec2]) -> Vec<Vec2> {
+    let (min, max) = points.iter().fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), p| (min.min(*p), max.max(*p)),
+    );
+    let center = (min + max) / 2.0;
+    let scale = SIZE / (max - min).max_element().max(f32::EPSILON);
+    points.iter().map(|p| (*p - center) * scale).collect()
+}
+
+/// The Hilbert curve closed up, a path with plenty of corners to ring around
+pub fn model(_app: &App) -> Model {
+    Model::from_reader(l_system::hilbert::reader())
+}
+
+/// The path and number of circles of an earlier run
+pub fn replay(run: &Run) -> Result<Model, String> {
+    match &run.params {
+        Params::Fourier { path, terms } => {
+            let mut model = Model::from_path(path.clone());
+            model.terms = *terms;
+            model.capture.run = Some(model.run());
+            Ok(model)
+        }
+        _ => Err(format!("{} is not a fourier run", run.sketch)),
+    }
+}
+
+pub fn update(_app: &App, model: &mut Model, update: Update) {
+    model.clock.tick(&update);
+}
+
+/// Up and down add or remove a circle, right and left double or halve the number
+pub fn event(_app: &App, model: &mut Model, event: Event) {
+    if let Event::WindowEvent {
+        simple: Some(WindowEvent::KeyPressed(key)),
+        ..
+    } = event
+    {
+        let most = model.epicycles.len().max(1);
+        model.terms = match key {
+            Key::Up => model.terms + 1,
+            Key::Down => model.terms.saturating_sub(1),
+            Key::Right => model.terms * 2,
+            Key::Left => model.terms / 2,
+            _ => model.terms,
+        }
+        .clamp(1, most);
+        model.capture.run = Some(model.run());
+    }
+}
+
+pub fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(BLACK);
+
+    let phase = model.timeline.phase(model.clock.frame());
+    let t = phase * TAU;
+    let epicycles = model.active();
+
+    // The path being approximated, faintly
+    draw.polyline().weight(1.0).points_colored(
+        model
+            .path
+            .iter()
+            .chain(model.path.first())
+            .map(|p| (*p, rgba(1.0, 1.0, 1.0, 0.15))),
+    );
+
+    // What the circles have drawn so far this loop
+    let traced = (TRACE_POINTS as f32 * phase) as usize;
+    draw.polyline()
+        .weight(2.0)
+        .points_colored((0..=traced).map(|i| {
+            let ti = TAU * i as f32 / TRACE_POINTS as f32;
+            (epicycle::tip(epicycles, Vec2::ZERO, ti.min(t)), STEELBLUE)
+        }));
+
+    epicycle::draw_chain(&draw, epicycles, Vec2::ZERO, t, rgba(0.8, 0.8, 0.8, 0.4));
+    draw.ellipse()
+        .radius(4.0)
+        .xy(epicycle::tip(epicycles, Vec2::ZERO, t))
+        .color(SALMON);
+
+    let window = app.window_rect();
+    draw.text(&format!(
+        "{} of {} circles",
+        epicycles.len(),
+        model.epicycles.len()
+    ))
+    .xy(pt2(window.left() + 120.0, window.top() - 20.0))
+    .w(220.0)
+    .font_size(14)
+    .color(WHITE);
+
+    draw.to_frame(app, &frame).unwrap();
+    // Press R to start and stop recording
+    model.capture.capture(app);
+}
@@ -0,0 +1,484 @@
+use nannou::{
+    color::rgba,
+    event::Update,
+    geom::Rect,
+    prelude::{Vec2, BLACK, PALEGOLDENROD, PALEGREEN, SLATEBLUE, WHITE},
+    App, Frame,
+};
+
+use crate::{
+    audio::{Binding, Signals},
+    capture::Session,
+    clock::Clock,
+    encode::{Encoding, Format},
+    l_system::{self, bush, peano_variety::Variant, SymbolReader},
+    maze::{breadth_first_maze, breadth_first_maze_growing, depth_first_maze, Maze},
+    palette::{named_color, Palette},
+    run::{Params, Run},
+    segment::{bounds, Segment},
+    stroke::Stroke,
+    style::Style,
+    transform::Transform,
+};
+
+/// Anything that grows a drawing one step at a time and can be shown in a gallery cell
+pub trait Exhibit {
+    /// Advance the drawing. Returns false once it is complete.
+    fn step(&mut self) -> bool;
+
+    /// Segments drawn so far, as layers drawn in order, each with the cursor stack depths of
+    /// its segments if there are any
+    fn layers(&self) -> Vec<(&[Segment], &[usize])>;
+
+    /// The area the finished drawing will fill, if known before it is finished
+    fn area(&self) -> Option<Rect> {
+        None
+    }
+}
+
+impl<E: Exhibit + ?Sized> Exhibit for Box<E> {
+    fn step(&mut self) -> bool {
+        (**self).step()
+    }
+
+    fn layers(&self) -> Vec<(&[Segment], &[usize])> {
+        (**self).layers()
+    }
+
+    fn area(&self) -> Option<Rect> {
+        (**self).area()
+    }
+}
+
+impl Exhibit for SymbolReader {
+    fn step(&mut self) -> bool {
+        self.advance()
+    }
+
+    fn layers(&self) -> Vec<(&[Segment], &[usize])> {
+        vec![(&self.segments, &self.depths)]
+    }
+}
+
+impl Exhibit for Maze {
+    fn step(&mut self) -> bool {
+        Maze::step(self)
+    }
+
+    fn layers(&self) -> Vec<(&[Segment], &[usize])> {
+        vec![(self.segments(), &[]), (self.finished_segments(), &[])]
+    }
+
+    fn area(&self) -> Option<Rect> {
+        Some(Maze::area(self))
+    }
+}
+
+/// One viewport of the gallery
+pub struct Cell {
+    pub exhibit: Box<dyn Exhibit>,
+    pub caption: String,
+    /// Style of each layer, the last is reused for any layers beyond it
+    pub styles: Vec<Style>,
+    /// What the exhibit was built from, if it came from a run
+    pub run: Option<Run>,
+    /// Area shown in the cell. Unless the exhibit knows its area this grows with the drawing
+    /// and never shrinks, so the view settles down rather than jumping around.
+    extent: Option<Rect>,
+    /// Segments of each layer already taken into the extent, so each step only measures the
+    /// segments added since
+    measured: Vec<usize>,
+    finished: bool,
+}
+
+impl Cell {
+    pub fn new(exhibit: impl Exhibit + 'static, caption: &str, style: Style) -> Self {
+        let extent = exhibit.area();
+        Cell {
+            exhibit: Box::new(exhibit),
+            caption: String::from(caption),
+            styles: vec![style],
+            run: None,
+            extent,
+            measured: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// The drawing the run describes, which the gallery's own run records
+    pub fn from_run(run: Run, caption: &str, style: Style) -> Result<Self, String> {
+        Ok(Cell {
+            run: Some(run.clone()),
+            ..Cell::new(run.exhibit()?, caption, style)
+        })
+    }
+
+    /// Add a style for the next layer
+    pub fn layer_style(mut self, style: Style) -> Self {
+        self.styles.push(style);
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn step(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = !self.exhibit.step();
+        if self.exhibit.area().is_none() {
+            let layers = self.exhibit.layers();
+            self.measured.resize(layers.len(), 0);
+            // New segments arrive at the end of a layer, and one that shrank is measured on from
+            // its new end
+            let added = layers
+                .iter()
+                .zip(self.measured.iter_mut())
+                .flat_map(|((segments, _), measured)| {
+                    let new = &segments[(*measured).min(segments.len())..];
+                    *measured = segments.len();
+                    new.iter().copied()
+                })
+                .collect::<Vec<Segment>>();
+            if let Some(b) = bounds(&added) {
+                self.extent = Some(match self.extent {
+                    Some(e) => union(e, b),
+                    None => b,
+                });
+            }
+        }
+    }
+
+    /// Draw the exhibit fitted into the viewport with its caption underneath
+    fn draw(&self, draw: &nannou::Draw, viewport: Rect) {
+        // Leave room at the bottom of the viewport for the caption
+        let caption_height = viewport.h() * 0.1;
+        let picture = Rect::from_x_y_w_h(
+            viewport.x(),
+            viewport.y() + caption_height * 0.5,
+            viewport.w(),
+            viewport.h() - caption_height,
+        );
+        if let Some(e) = self.extent {
+            let scale = (picture.w() / e.w().max(1.0)).min(picture.h() / e.h().max(1.0)) * 0.9;
+            let fit = Transform::translate(-Vec2::new(e.x(), e.y()))
+                .then(Transform::scale(scale))
+                .then(Transform::translate(Vec2::new(picture.x(), picture.y())));
+            for (i, (segments, depths)) in self.exhibit.layers().into_iter().enumerate() {
+                if let Some(style) = self.styles.get(i).or_else(|| self.styles.last()) {
+                    style.draw_with_depths(draw, &fit.apply_segments(segments), depths);
+                }
+            }
+        }
+        draw.text(&self.caption)
+            .xy(Vec2::new(
+                viewport.x(),
+                viewport.bottom() + caption_height * 0.5,
+            ))
+            .w(viewport.w())
+            .font_size(14)
+            .color(WHITE);
+    }
+}
+
+fn union(a: Rect, b: Rect) -> Rect {
+    Rect::from_corners(
+        Vec2::new(a.left().min(b.left()), a.bottom().min(b.bottom())),
+        Vec2::new(a.right().max(b.right()), a.top().max(b.top())),
+    )
+}
+
+/// A grid of cells growing side by side in one window
+pub struct Gallery {
+    pub cells: Vec<Cell>,
+    /// Zero to pick the column count that makes the grid closest to square
+    pub columns: usize,
+    /// Steps each cell takes per update
+    pub steps_per_update: usize,
+    /// Soundtrack setting the steps each cell takes per frame in place of steps_per_update
+    pub growth: Option<(Signals, Binding)>,
+    clock: Clock,
+    /// Records the whole grid. Once every cell has finished the recording stops.
+    pub capture: Session,
+}
+
+impl Gallery {
+    pub fn new(cells: Vec<Cell>) -> Self {
+        Gallery {
+            cells,
+            columns: 0,
+            steps_per_update: 1,
+            growth: None,
+            clock: Clock::from_env(30),
+            capture: Session::new("gallery").encode(Format::Mp4, Encoding::default()),
+        }
+        .describe()
+    }
+
+    pub fn columns(mut self, columns: usize) -> Self {
+        self.columns = columns;
+        self.describe()
+    }
+
+    pub fn steps_per_update(mut self, steps: usize) -> Self {
+        self.steps_per_update = steps;
+        self.describe()
+    }
+
+    /// The gallery as a run, if every cell came from one
+    pub fn run(&self) -> Option<Run> {
+        let cells = self
+            .cells
+            .iter()
+            .map(|c| Some((c.caption.clone(), c.run.clone()?, c.styles.clone())))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Run::new(
+            "gallery",
+            Params::Gallery {
+                columns: self.columns,
+                steps_per_update: self.steps_per_update,
+                cells,
+            },
+        ))
+    }
+
+    /// So the next recording describes the gallery as it is now
+    fn describe(mut self) -> Self {
+        self.capture.run = self.run();
+        self
+    }
+
+    /// Grow at the speed the binding gives for each frame of the signals, running at their
+    /// frame rate. The soundtrack is added to the encodings of the capture, whether it is set
+    /// before or after.
+    pub fn growth(mut self, signals: Signals, binding: Binding) -> Self {
+        self.clock = Clock::from_env(signals.fps);
+        self.growth = Some((signals, binding));
+        self.dub();
+        self
+    }
+
+    /// Add the soundtrack of the growth, if it has one, to every encoding of the capture
+    fn dub(&mut self) {
+        if let Some((signals, _)) = &self.growth {
+            if let Some(path) = &signals.path {
+                for (_, encoding) in self.capture.encodings.iter_mut() {
+                    *encoding = encoding.clone().audio(path).fps(signals.fps);
+                }
+            }
+        }
+    }
+
+    /// Record with the session, describing the gallery unless the session has a run already.
+    /// The soundtrack of the growth goes into its encodings.
+    pub fn capture(mut self, session: Session) -> Self {
+        let run = session.run.clone().or_else(|| self.run());
+        self.capture = session;
+        self.capture.run = run;
+        self.dub();
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cells.iter().all(|c| c.is_finished())
+    }
+
+    /// The viewport of each cell, in order, filling the rect row by row from the top left
+    pub fn viewports(&self, rect: Rect) -> Vec<Rect> {
+        let n = self.cells.len().max(1);
+        let columns = if self.columns == 0 {
+            (n as f32).sqrt().ceil() as usize
+        } else {
+            self.columns
+        };
+        let rows = n.div_ceil(columns);
+        let (w, h) = (rect.w() / columns as f32, rect.h() / rows as f32);
+        (0..self.cells.len())
+            .map(|i| {
+                Rect::from_x_y_w_h(
+                    rect.left() + w * ((i % columns) as f32 + 0.5),
+                    rect.top() - h * ((i / columns) as f32 + 0.5),
+                    w,
+                    h,
+                )
+            })
+            .collect()
+    }
+}
+
+pub fn update(app: &App, model: &mut Gallery, update: Update) {
+    model.clock.tick(&update);
+    let steps = match &model.growth {
+        // The speed of each frame since the last update
+        Some((signals, binding)) => {
+            let last = model.clock.frame();
+            (last - model.clock.steps()..last)
+                .map(|frame| binding.value(signals, frame).round().max(0.0) as usize)
+                .sum()
+        }
+        None => model.steps_per_update,
+    };
+    for cell in model.cells.iter_mut() {
+        for _ in 0..steps {
+            cell.step();
+        }
+    }
+    if model.is_finished() {
+        model.capture.stop(app);
+    }
+}
+
+pub fn view(app: &App, model: &Gallery, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(BLACK);
+
+    for (cell, viewport) in model.cells.iter().zip(model.viewports(app.window_rect())) {
+        cell.draw(&draw, viewport);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+
+    // Press R to start and stop recording
+    model.capture.capture(app);
+}
+
+/// A selection of presets, depths and seeds
+pub fn model(_app: &App) -> Gallery {
+    selection()
+}
+
+fn selection() -> Gallery {
+    let gold = || Style::new(Palette::Solid(rgba(0.776, 0.811, 0.266, 1.0)), 1.0);
+    let green = || Style::new(Palette::Solid(rgba(0.5, 0.9, 0.266, 0.3)), 1.0);
+    let drawing = |sketch: &str, recipe| Run::new(sketch, Params::LSystem(recipe));
+    let maze = |sketch: &str, plan| Run::new(sketch, Params::Maze(plan));
+    let cell = |run, caption: &str, style| Cell::from_run(run, caption, style).unwrap();
+    Gallery::new(vec![
+        cell(drawing("tree", l_system::tree::recipe()), "tree", green()),
+        cell(drawing("bush", bush::recipe(1)), "bush seed 1", green()),
+        cell(drawing("bush", bush::recipe(2)), "bush seed 2", green()),
+        cell(
+            drawing("peano_variety", Variant::ORIGINAL.recipe(2)),
+            "peano variety depth 2",
+            gold(),
+        ),
+        cell(
+            drawing("peano_variety", Variant::ORIGINAL.recipe(3)),
+            "peano variety depth 3",
+            gold(),
+        ),
+        cell(
+            drawing("peano_gosper", l_system::peano_gosper::recipe()),
+            "peano gosper",
+            gold(),
+        ),
+        cell(
+            maze("depth_first_maze", depth_first_maze::plan(1)),
+            "depth first maze seed 1",
+            Style::new(Palette::Solid(named_color(SLATEBLUE, 1.0)), 1.0),
+        )
+        .layer_style(Style::new(
+            Palette::Solid(named_color(PALEGOLDENROD, 1.0)),
+            3.0,
+        )),
+        cell(
+            maze("breadth_first_maze", breadth_first_maze::plan(1)),
+            "breadth first maze seed 1",
+            Style::new(Palette::Solid(named_color(PALEGOLDENROD, 1.0)), 3.0),
+        ),
+        cell(
+            maze(
+                "breadth_first_maze_growing",
+                breadth_first_maze_growing::plan(1),
+            ),
+            "growing maze seed 1",
+            Style::new(Palette::Solid(named_color(PALEGREEN, 1.0)), 1.0)
+                .stroke(Stroke::ramp(3.0, 0.5)),
+        ),
+    ])
+    .steps_per_update(4)
+}
+
+/// The cells, styles and speed of an earlier gallery run
+pub fn replay(run: &Run) -> Result<Gallery, String> {
+    match &run.params {
+        Params::Gallery {
+            columns,
+            steps_per_update,
+            cells,
+        } => {
+            let cells = cells
+                .iter()
+                .map(|(caption, run, styles)| {
+                    let (first, rest) = styles
+                        .split_first()
+                        .ok_or_else(|| format!("{} has no style", caption))?;
+                    let cell = Cell::from_run(run.clone(), caption, first.clone())?;
+                    Ok(rest.iter().cloned().fold(cell, Cell::layer_style))
+                })
+                .collect::<Result<Vec<Cell>, String>>()?;
+            Ok(Gallery::new(cells)
+                .columns(*columns)
+                .steps_per_update(*steps_per_update))
+        }
+        _ => Err(format!("{} is not a gallery run", run.sketch)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::audio::{Audio, Source};
+
+    /// Replaying a gallery's run should rebuild every cell with the same drawing and styles
+    #[test]
+    fn selection_replays() {
+        let mut gallery = selection();
+        let run = gallery.capture.run.clone().unwrap();
+        let mut replayed = replay(&Run::parse(&run.to_toml()).unwrap()).unwrap();
+        assert_eq!(
+            replayed.capture.run.as_ref().unwrap().to_toml(),
+            run.to_toml()
+        );
+        assert_eq!(replayed.cells.len(), gallery.cells.len());
+        for (a, b) in gallery.cells.iter_mut().zip(replayed.cells.iter_mut()) {
+            assert_eq!(a.styles.len(), b.styles.len(), "{}", a.caption);
+            for _ in 0..200 {
+                a.step();
+                b.step();
+            }
+            let ends = |cell: &Cell| {
+                let layers = cell.exhibit.layers().into_iter();
+                layers
+                    .flat_map(|(segments, _)| segments.iter().map(|s| (s.start(), s.end())))
+                    .collect::<Vec<(Vec2, Vec2)>>()
+            };
+            assert_eq!(ends(a), ends(b), "{}", a.caption);
+        }
+    }
+
+    /// The soundtrack should reach the encodings of a capture set after the growth as well as one
+    /// set before
+    #[test]
+    fn growth_soundtrack_reaches_the_capture() {
+        let audio = Audio {
+            sample_rate: 8000,
+            samples: vec![0.5; 8000],
+        };
+        let mut signals = audio.signals(24, 4);
+        signals.path = Some(PathBuf::from("beat.wav"));
+        let binding = Binding::new(Source::Amplitude, 1.0, 4.0);
+        let gallery = selection()
+            .growth(signals, binding)
+            .capture(Session::new("gallery").encode(Format::Gif, Encoding::default()));
+        for (_, encoding) in gallery.capture.encodings.iter() {
+            assert_eq!(encoding.audio, Some(PathBuf::from("beat.wav")));
+            assert_eq!(encoding.fps, 24);
+        }
+        assert_eq!(gallery.capture.encodings.len(), 1);
+    }
+}
@@ -0,0 +1,264 @@
+use std::{fmt::Write, fs, path::PathBuf};
+
+use nannou::prelude::Vec2;
+
+use crate::{segment::Segment, svg};
+
+// Reference dumps live in /golden. Set UPDATE_GOLDEN=1 when running the tests to rewrite them
+// after an intentional change to a preset.
+const UPDATE_VAR: &str = "UPDATE_GOLDEN";
+
+/// Largest distance an endpoint may move before it counts as a change
+pub const TOLERANCE: f32 = 0.05;
+
+fn reference_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("golden")
+        .join(name)
+        .with_extension("txt")
+}
+
+fn report_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("target")
+        .join("golden")
+}
+
+/// Write the segments in a stable text format, one segment per line
+pub fn dump(segments: &[Segment]) -> String {
+    let mut out = String::new();
+    for s in segments {
+        writeln!(
+            out,
+            "{:.3} {:.3} {:.3} {:.3}",
+            s.start().x,
+            s.start().y,
+            s.end().x,
+            s.end().y
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Read segments back from the format written by dump
+pub fn parse(text: &str) -> Result<Vec<Segment>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            let v = line
+                .split_whitespace()
+                .map(|x| x.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|e| format!("line {}: {}", n + 1, e))?;
+            match v[..] {
+                [x0, y0, x1, y1] => Ok(Segment::from((Vec2::new(x0, y0), Vec2::new(x1, y1)))),
+                _ => Err(format!("line {}: expected four numbers", n + 1)),
+            }
+        })
+        .collect()
+}
+
+/// A segment that differs between the reference and the current output
+#[derive(Debug, Copy, Clone)]
+pub struct Mismatch {
+    pub index: usize,
+    pub expected: Option<Segment>,
+    pub actual: Option<Segment>,
+}
+
+/// Compare segments in order, allowing each endpoint to move by up to tolerance
+pub fn compare(expected: &[Segment], actual: &[Segment], tolerance: f32) -> Vec<Mismatch> {
+    let n = expected.len().max(actual.len());
+    (0..n)
+        .filter_map(|index| {
+            let e = expected.get(index).copied();
+            let a = actual.get(index).copied();
+            let same = match (e, a) {
+                (Some(e), Some(a)) => {
+                    e.start().distance(a.start()) <= tolerance
+                        && e.end().distance(a.end()) <= tolerance
+                }
+                _ => false,
+            };
+            (!same).then_some(Mismatch {
+                index,
+                expected: e,
+                actual: a,
+            })
+        })
+        .collect()
+}
+
+/// Human readable summary of the mismatches, listing at most the first twenty
+pub fn report(
+    name: &str,
+    expected: &[Segment],
+    actual: &[Segment],
+    mismatches: &[Mismatch],
+) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "{}: {} of {} segments differ (reference has {}, output has {})",
+        name,
+        mismatches.len(),
+        expected.len().max(actual.len()),
+        expected.len(),
+        actual.len()
+    )
+    .unwrap();
+    for m in mismatches.iter().take(20) {
+        let fmt = |s: Option<Segment>| match s {
+            Some(s) => format!("{} -> {}", s.start(), s.end()),
+            None => String::from("missing"),
+        };
+        writeln!(
+            out,
+            "  #{}: expected {}, got {}",
+            m.index,
+            fmt(m.expected),
+            fmt(m.actual)
+        )
+        .unwrap();
+    }
+    if mismatches.len() > 20 {
+        writeln!(out, "  ... and {} more", mismatches.len() - 20).unwrap();
+    }
+    out
+}
+
+/// Compare the segments against the reference dump with the given name.
+///
+/// Every reference is recorded instead when UPDATE_GOLDEN is set, and a missing one fails
+/// otherwise. On failure a text report and an SVG overlay (reference in grey, output in blue,
+/// changed segments in red) are written to target/golden.
+pub fn check(name: &str, actual: &[Segment]) -> Result<(), String> {
+    let path = reference_path(name);
+    if std::env::var_os(UPDATE_VAR).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).map_err(|e| e.to_string())?;
+        fs::write(&path, dump(actual)).map_err(|e| e.to_string())?;
+        eprintln!("recorded reference {}", path.display());
+        return Ok(());
+    }
+    if !path.exists() {
+        return Err(format!(
+            "{}: no reference at {}, run the tests with {}=1 to record it",
+            name,
+            path.display(),
+            UPDATE_VAR
+        ));
+    }
+
+    let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let expected = parse(&text)?;
+    let mismatches = compare(&expected, actual, TOLERANCE);
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    let changed = mismatches
+        .iter()
+        .filter_map(|m| m.actual.or(m.expected))
+        .collect::<Vec<Segment>>();
+    let mut summary = report(name, &expected, actual, &mismatches);
+
+    let dir = report_dir();
+    let written = fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(dir.join(format!("{}.diff.txt", name)), &summary))
+        .and_then(|_| {
+            svg::save(
+                dir.join(format!("{}.diff.svg", name)),
+                &[
+                    svg::Layer::new(&expected, "#aaaaaa", 3.0),
+                    svg::Layer::new(actual, "#3366cc", 1.0),
+                    svg::Layer::new(&changed, "#ee2222", 1.5),
+                ],
+                10.0,
+            )
+        });
+    match written {
+        Ok(()) => writeln!(summary, "diff written to {}", dir.display()).unwrap(),
+        Err(e) => writeln!(summary, "unable to write diff: {}", e).unwrap(),
+    }
+    Err(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::{l_system, maze, segment::Segment};
+
+    fn expect(name: &str, segments: &[Segment]) {
+        if let Err(report) = check(name, segments) {
+            panic!("{}", report)
+        }
+    }
+
+    fn preset(name: &str, mut reader: l_system::SymbolReader) {
+        reader.read_all();
+        expect(name, &reader.segments)
+    }
+
+    #[test]
+    fn bush() {
+        preset("bush", l_system::bush::reader(Some(26)))
+    }
+
+    #[test]
+    fn corn() {
+        preset("corn", l_system::corn::reader())
+    }
+
+    #[test]
+    fn fern() {
+        preset("fern", l_system::fern::reader())
+    }
+
+    #[test]
+    fn hilbert() {
+        preset("hilbert", l_system::hilbert::reader())
+    }
+
+    #[test]
+    fn peano() {
+        preset("peano", l_system::peano::reader())
+    }
+
+    #[test]
+    fn peano_gosper() {
+        preset("peano_gosper", l_system::peano_gosper::reader())
+    }
+
+    #[test]
+    fn peano_variety() {
+        preset("peano_variety", l_system::peano_variety::reader())
+    }
+
+    #[test]
+    fn tree() {
+        preset("tree", l_system::tree::reader())
+    }
+
+    #[test]
+    fn depth_first_maze() {
+        let mut m = maze::depth_first_maze::seeded(26);
+        while m.step() {}
+        expect("depth_first_maze", m.finished_segments())
+    }
+
+    #[test]
+    fn breadth_first_maze() {
+        let mut m = maze::breadth_first_maze::seeded(26);
+        while m.step() {}
+        expect("breadth_first_maze", m.segments())
+    }
+
+    #[test]
+    fn breadth_first_maze_growing() {
+        let mut m = maze::breadth_first_maze_growing::seeded(26);
+        while m.step() {}
+        expect("breadth_first_maze_growing", m.segments())
+    }
+}
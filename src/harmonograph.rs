@@ -0,0 +1,352 @@
+use std::{
+    f32::consts::{FRAC_PI_2, FRAC_PI_4},
+    path::Path,
+};
+
+use nannou::{
+    prelude::*,
+    rand::{random, rngs::StdRng, Rng, SeedableRng},
+};
+
+use crate::{
+    capture::Session,
+    clock::Clock,
+    encode::{Encoding, Format},
+    gallery::Exhibit,
+    run::{sidecar_path, Params, Run},
+    segment::Segment,
+    svg,
+};
+
+/// A swinging pendulum whose swing dies away exponentially
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pendulum {
+    /// Radians per second
+    pub frequency: f32,
+    pub phase: f32,
+    pub amplitude: f32,
+    /// Rate the swing dies away, 0 for a swing that never does
+    pub decay: f32,
+}
+
+impl Pendulum {
+    pub fn new(frequency: f32, phase: f32, amplitude: f32, decay: f32) -> Self {
+        Pendulum {
+            frequency,
+            phase,
+            amplitude,
+            decay,
+        }
+    }
+
+    /// Displacement at the given time
+    pub fn at(&self, t: f32) -> f32 {
+        self.amplitude * (self.frequency * t + self.phase).sin() * (-self.decay * t).exp()
+    }
+}
+
+/// A pen moved by pendulums, those on each axis adding together
+#[derive(Debug, Clone, PartialEq)]
+pub struct Harmonograph {
+    pub x: Vec<Pendulum>,
+    pub y: Vec<Pendulum>,
+}
+
+impl Harmonograph {
+    /// The Lissajous figure with the given frequencies and phase difference, which never decays
+    pub fn lissajous(a: f32, b: f32, delta: f32, size: f32) -> Self {
+        Harmonograph {
+            x: vec![Pendulum::new(a, delta, size, 0.0)],
+            y: vec![Pendulum::new(b, 0.0, size, 0.0)],
+        }
+    }
+
+    /// Four pendulums at frequencies close to small whole number ratios, so the trace drifts
+    /// slowly between related figures as it decays
+    pub fn random(seed: u64, size: f32) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let base = rng.gen_range(1..=4) as f32;
+        let pendulum = |rng: &mut StdRng, share: f32| {
+            let ratio = rng.gen_range(1..=4) as f32 + rng.gen_range(-0.02..0.02);
+            Pendulum::new(
+                ratio / base * 2.0,
+                rng.gen_range(0.0..TAU),
+                size * share,
+                rng.gen_range(0.002..0.02),
+            )
+        };
+        let share = rng.gen_range(0.3..0.7);
+        Harmonograph {
+            x: vec![pendulum(&mut rng, share), pendulum(&mut rng, 1.0 - share)],
+            y: vec![pendulum(&mut rng, share), pendulum(&mut rng, 1.0 - share)],
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Vec2 {
+        Vec2::new(
+            self.x.iter().map(|p| p.at(t)).sum(),
+            self.y.iter().map(|p| p.at(t)).sum(),
+        )
+    }
+
+    /// Points of the trace from time 0 to duration
+    pub fn points(&self, duration: f32, steps: usize) -> Vec<Vec2> {
+        let steps = steps.max(1);
+        (0..=steps)
+            .map(|i| self.at(duration * i as f32 / steps as f32))
+            .collect()
+    }
+
+    pub fn segments(&self, duration: f32, steps: usize) -> Vec<Segment> {
+        self.points(duration, steps)
+            .windows(2)
+            .map(|w| Segment::from((w[0], w[1])))
+            .collect()
+    }
+
+    /// Write the trace as an SVG with a sidecar recording the pendulums
+    pub fn save_svg(
+        &self,
+        name: &str,
+        path: impl AsRef<Path>,
+        duration: f32,
+        steps: usize,
+    ) -> std::io::Result<()> {
+        let path = path.as_ref();
+        svg::save(
+            path,
+            &[svg::Layer::new(
+                &self.segments(duration, steps),
+                "#2f4f6f",
+                1.0,
+            )],
+            10.0,
+        )?;
+        Run::new(name, Params::Harmonograph(self.clone()))
+            .stamp()
+            .save(sidecar_path(path))
+    }
+}
+
+// Radius of the figures
+const SIZE: f32 = 300.0;
+// Length of a whole trace in pendulum seconds and the points it is drawn with
+pub const DURATION: f32 = 200.0;
+pub const STEPS: usize = 20000;
+
+/// Named settings worth coming back to
+pub fn presets() -> Vec<(&'static str, Harmonograph)> {
+    vec![
+        (
+            "lissajous 1:2",
+            Harmonograph::lissajous(1.0, 2.0, FRAC_PI_2, SIZE),
+        ),
+        (
+            "lissajous 3:2",
+            Harmonograph::lissajous(3.0, 2.0, FRAC_PI_2, SIZE),
+        ),
+        (
+            "lissajous 5:4",
+            Harmonograph::lissajous(5.0, 4.0, FRAC_PI_4, SIZE),
+        ),
+        (
+            "rosette",
+            Harmonograph {
+                x: vec![
+                    Pendulum::new(2.0, 0.0, 180.0, 0.004),
+                    Pendulum::new(3.01, FRAC_PI_2, 120.0, 0.006),
+                ],
+                y: vec![
+                    Pendulum::new(2.0, FRAC_PI_2, 180.0, 0.004),
+                    Pendulum::new(3.01, PI, 120.0, 0.006),
+                ],
+            },
+        ),
+        (
+            "drift",
+            Harmonograph {
+                x: vec![
+                    Pendulum::new(3.0, 0.0, 150.0, 0.008),
+                    Pendulum::new(2.0, 1.0, 150.0, 0.003),
+                ],
+                y: vec![
+                    Pendulum::new(2.005, 0.5, 150.0, 0.008),
+                    Pendulum::new(3.0, 0.0, 150.0, 0.003),
+                ],
+            },
+        ),
+        (
+            "spiral",
+            Harmonograph {
+                x: vec![Pendulum::new(1.0, FRAC_PI_2, SIZE, 0.015)],
+                y: vec![Pendulum::new(1.0, 0.0, SIZE, 0.015)],
+            },
+        ),
+    ]
+}
+
+/// Draws the trace a step at a time, so it can grow in a gallery
+pub struct Tracer {
+    pub harmonograph: Harmonograph,
+    segments: Vec<Segment>,
+    duration: f32,
+    steps: usize,
+}
+
+impl Tracer {
+    pub fn new(harmonograph: Harmonograph, duration: f32, steps: usize) -> Self {
+        Tracer {
+            harmonograph,
+            segments: Vec::new(),
+            duration,
+            steps: steps.max(1),
+        }
+    }
+}
+
+impl Exhibit for Tracer {
+    fn step(&mut self) -> bool {
+        let i = self.segments.len();
+        if i >= self.steps {
+            return false;
+        }
+        let dt = self.duration / self.steps as f32;
+        self.segments.push(Segment::from((
+            self.harmonograph.at(dt * i as f32),
+            self.harmonograph.at(dt * (i + 1) as f32),
+        )));
+        true
+    }
+
+    fn layers(&self) -> Vec<(&[Segment], &[usize])> {
+        vec![(&self.segments, &[])]
+    }
+
+    fn area(&self) -> Option<Rect> {
+        let reach = |ps: &[Pendulum]| ps.iter().map(|p| p.amplitude.abs()).sum::<f32>() * 2.0;
+        Some(Rect::from_w_h(
+            reach(&self.harmonograph.x),
+            reach(&self.harmonograph.y),
+        ))
+    }
+}
+
+pub struct Model {
+    clock: Clock,
+    capture: Session,
+    /// Index into the presets, None once a random harmonograph is showing
+    preset: Option<usize>,
+    name: String,
+    harmonograph: Harmonograph,
+    /// Clock time the current trace started
+    started: f32,
+}
+
+// Pendulum seconds traced per second
+const SPEED: f32 = 10.0;
+
+impl Model {
+    fn show(&mut self, name: String, harmonograph: Harmonograph) {
+        // So the next recording describes the harmonograph on screen
+        self.capture.run = Some(Run::new(
+            "harmonograph",
+            Params::Harmonograph(harmonograph.clone()),
+        ));
+        self.name = name;
+        self.harmonograph = harmonograph;
+        self.started = self.clock.time();
+    }
+}
+
+pub fn model(_app: &App) -> Model {
+    let (name, harmonograph) = presets().swap_remove(0);
+    Model {
+        clock: Clock::from_env(30),
+        capture: Session::new("harmonograph")
+            .run(Run::new(
+                "harmonograph",
+                Params::Harmonograph(harmonograph.clone()),
+            ))
+            .encode(Format::Mp4, Encoding::default()),
+        preset: Some(0),
+        name: String::from(name),
+        harmonograph,
+        started: 0.0,
+    }
+}
+
+pub fn update(_app: &App, model: &mut Model, update: Update) {
+    model.clock.tick(&update);
+}
+
+/// Right shows the next preset, space a random harmonograph and S saves the trace as an SVG
+pub fn event(app: &App, model: &mut Model, event: Event) {
+    if let Event::WindowEvent {
+        simple: Some(WindowEvent::KeyPressed(key)),
+        ..
+    } = event
+    {
+        match key {
+            Key::Right => {
+                let presets = presets();
+                let i = model.preset.map_or(0, |i| (i + 1) % presets.len());
+                let (name, harmonograph) = presets[i].clone();
+                model.preset = Some(i);
+                model.show(String::from(name), harmonograph);
+            }
+            Key::Space => {
+                let seed = random::<u64>();
+                model.preset = None;
+                model.show(format!("random {}", seed), Harmonograph::random(seed, SIZE));
+            }
+            Key::S => {
+                let file = format!("{}.svg", model.name.replace([' ', ':'], "_"));
+                let result = app
+                    .project_path()
+                    .map_err(|e| e.to_string())
+                    .map(|p| p.join("harmonograph"))
+                    .and_then(|dir| {
+                        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+                        model
+                            .harmonograph
+                            .save_svg("harmonograph", dir.join(&file), DURATION, STEPS)
+                            .map_err(|e| e.to_string())?;
+                        Ok(dir.join(&file))
+                    });
+                match result {
+                    Ok(path) => println!("saved {}", path.display()),
+                    Err(e) => eprintln!("failed to save {}: {}", file, e),
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+pub fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(rgb(0.96, 0.94, 0.88));
+
+    // Draw as far as the pendulums have swung since the trace started
+    let t = ((model.clock.time() - model.started) * SPEED).min(DURATION);
+    let steps = (STEPS as f32 * t / DURATION) as usize;
+    let points = model.harmonograph.points(t, steps.max(1));
+    let count = points.len() as f32;
+    draw.polyline().weight(1.0).points_colored(
+        points
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| (p, rgba(0.18, 0.31, 0.44, 0.3 + 0.7 * i as f32 / count))),
+    );
+
+    let window = app.window_rect();
+    draw.text(&model.name)
+        .xy(pt2(window.left() + 120.0, window.top() - 20.0))
+        .w(220.0)
+        .font_size(14)
+        .color(BLACK);
+
+    draw.to_frame(app, &frame).unwrap();
+    // Press R to start and stop recording
+    model.capture.capture(app);
+}
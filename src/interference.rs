@@ -0,0 +1,482 @@
+use std::path::{Path, PathBuf};
+
+use nannou::{
+    image::{Rgba as Pixel, RgbaImage},
+    prelude::*,
+};
+
+use crate::{
+    capture::Session,
+    clock::Clock,
+    encode::{self, Encoding, Format},
+    palette::Palette,
+    run::{sidecar_path, Params, Run},
+    segment::Segment,
+    timeline::Timeline,
+};
+
+use lazy_static::lazy_static;
+
+/// A point sending out circular waves
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Source {
+    pub position: Vec2,
+    /// Waves per second
+    pub frequency: f32,
+    /// Radians
+    pub phase: f32,
+    pub amplitude: f32,
+}
+
+// Distance over which a wave loses about a third of its height as it spreads
+const FALLOFF: f32 = 200.0;
+
+impl Source {
+    pub fn new(position: Vec2, frequency: f32, phase: f32, amplitude: f32) -> Self {
+        Source {
+            position,
+            frequency,
+            phase,
+            amplitude,
+        }
+    }
+
+    /// Height of the wave from this source at the point and time, for waves moving at the speed
+    pub fn height(&self, p: Vec2, t: f32, speed: f32) -> f32 {
+        let r = self.position.distance(p);
+        let wave = (TAU * self.frequency * (t - r / speed) + self.phase).sin();
+        // Spreading in a circle weakens the wave with the square root of the distance
+        self.amplitude * wave / (1.0 + r / FALLOFF).sqrt()
+    }
+}
+
+/// Waves from several sources added together
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub sources: Vec<Source>,
+    /// Distance a wave crest travels per second
+    pub speed: f32,
+}
+
+impl Field {
+    pub fn height(&self, p: Vec2, t: f32) -> f32 {
+        self.sources
+            .iter()
+            .map(|s| s.height(p, t, self.speed))
+            .sum()
+    }
+
+    /// The highest the field could reach, where every wave peaks together
+    pub fn peak(&self) -> f32 {
+        self.sources
+            .iter()
+            .map(|s| s.amplitude.abs())
+            .sum::<f32>()
+            .max(f32::EPSILON)
+    }
+
+    /// Heights on a grid of points covering the rect, row by row from the bottom left
+    pub fn sample(&self, rect: Rect, columns: usize, rows: usize, t: f32) -> Vec<f32> {
+        let mut heights = Vec::with_capacity(columns * rows);
+        for j in 0..rows {
+            for i in 0..columns {
+                heights.push(self.height(grid_point(rect, columns, rows, i, j), t));
+            }
+        }
+        heights
+    }
+
+    /// Index of the source within the distance of the point, the nearest if there are several
+    pub fn nearest(&self, p: Vec2, within: f32) -> Option<usize> {
+        self.sources
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (i, s.position.distance(p)))
+            .filter(|(_, d)| *d <= within)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+}
+
+fn grid_point(rect: Rect, columns: usize, rows: usize, i: usize, j: usize) -> Vec2 {
+    let fraction = |k: usize, n: usize| k as f32 / (n.max(2) - 1) as f32;
+    Vec2::new(
+        rect.left() + rect.w() * fraction(i, columns),
+        rect.bottom() + rect.h() * fraction(j, rows),
+    )
+}
+
+/// Lines along the given height through a grid of heights laid out as by Field::sample, found
+/// by marching squares
+pub fn contours(
+    heights: &[f32],
+    rect: Rect,
+    columns: usize,
+    rows: usize,
+    level: f32,
+) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let at = |i: usize, j: usize| heights[j * columns + i];
+    for j in 0..rows.saturating_sub(1) {
+        for i in 0..columns.saturating_sub(1) {
+            // Corners anticlockwise from the bottom left
+            let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+            let values = corners.map(|(ci, cj)| at(ci, cj));
+            // Where the level crosses the bottom, right, top and left edges in turn
+            let crossings = (0..4)
+                .filter_map(|e| {
+                    let (a, b) = (values[e], values[(e + 1) % 4]);
+                    if (a >= level) == (b >= level) {
+                        return None;
+                    }
+                    let (pa, pb) = (corners[e], corners[(e + 1) % 4]);
+                    let pa = grid_point(rect, columns, rows, pa.0, pa.1);
+                    let pb = grid_point(rect, columns, rows, pb.0, pb.1);
+                    Some(pa.lerp(pb, (level - a) / (b - a)))
+                })
+                .collect::<Vec<Vec2>>();
+            match crossings.as_slice() {
+                [a, b] => segments.push(Segment::from((*a, *b))),
+                // A saddle, the average height decides which opposite corners are joined
+                [bottom, right, top, left] => {
+                    let center = values.iter().sum::<f32>() / 4.0;
+                    if (center >= level) == (values[0] >= level) {
+                        segments.push(Segment::from((*bottom, *right)));
+                        segments.push(Segment::from((*top, *left)));
+                    } else {
+                        segments.push(Segment::from((*left, *bottom)));
+                        segments.push(Segment::from((*right, *top)));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    segments
+}
+
+/// How the field is shown
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Render {
+    /// Every point coloured by its height
+    Heightfield,
+    /// Lines of equal height
+    Contours,
+}
+
+impl Render {
+    fn next(self) -> Self {
+        match self {
+            Render::Heightfield => Render::Contours,
+            Render::Contours => Render::Heightfield,
+        }
+    }
+}
+
+// Number of contour lines between the lowest and highest heights
+const LEVELS: usize = 8;
+
+/// Heights at which contours are drawn, evenly spaced and leaving out the extremes
+fn levels(peak: f32) -> Vec<f32> {
+    (1..=LEVELS)
+        .map(|l| -peak + 2.0 * peak * l as f32 / (LEVELS + 1) as f32)
+        .collect()
+}
+
+/// Draw the field at the time as an image in the palette, without needing a window. The image
+/// is centered on the origin with one pixel per unit of distance.
+pub fn image(
+    field: &Field,
+    width: u32,
+    height: u32,
+    t: f32,
+    render: Render,
+    palette: &Palette,
+) -> RgbaImage {
+    let rect = Rect::from_w_h(width as f32, height as f32);
+    let heights = field.sample(rect, width as usize, height as usize, t);
+    // Peaks are rare, so the colours are stretched over half the highest possible height
+    let range = field.peak() * 0.5;
+    let band = |h: f32| ((h + field.peak()) / (2.0 * field.peak()) * (LEVELS + 1) as f32) as i32;
+    RgbaImage::from_fn(width, height, |x, y| {
+        // Rows of the image go down from the top
+        let (i, j) = (x as usize, (height - 1 - y) as usize);
+        let h = heights[j * width as usize + i];
+        let color = match render {
+            Render::Heightfield => palette.map(h, -range, range),
+            Render::Contours => {
+                // On a line if the next pixel across or up is in a different band
+                let right = heights
+                    .get(j * width as usize + i + 1)
+                    .filter(|_| i + 1 < width as usize);
+                let up = heights.get((j + 1) * width as usize + i);
+                let edge = [right, up].iter().flatten().any(|n| band(**n) != band(h));
+                if edge {
+                    palette.map(h, -range, range)
+                } else {
+                    rgba(0.0, 0.0, 0.0, 1.0)
+                }
+            }
+        };
+        Pixel([color.red, color.green, color.blue, color.alpha].map(|c| (c * 255.0) as u8))
+    })
+}
+
+lazy_static! {
+    /// The palette named by PALETTE, or ocean
+    static ref PALETTE: Palette =
+        Palette::from_env().unwrap_or_else(|| Palette::named("ocean").expect("ocean is built in"));
+}
+
+fn palette() -> &'static Palette {
+    &PALETTE
+}
+
+/// Write one loop of the field as numbered PNGs in the folder and encode them to an MP4 beside
+/// it, all without a window. Returns the path of the animation.
+pub fn render(
+    field: &Field,
+    dir: impl AsRef<Path>,
+    timeline: Timeline,
+    (width, height): (u32, u32),
+    mode: Render,
+    palette: &Palette,
+) -> Result<PathBuf, String> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+    for frame in 0..timeline.frames() {
+        let t = timeline.phase(frame) * timeline.period;
+        let path = dir.join(format!("{:04}.png", frame));
+        image(field, width, height, t, mode, palette)
+            .save(&path)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+    }
+    let params = Params::Interference {
+        field: field.clone(),
+        render: mode,
+        palette: palette.clone(),
+    };
+    Run::new("interference", params)
+        .window(width, height)
+        .stamp()
+        .save(sidecar_path(dir))
+        .map_err(|e| format!("failed to write run for {}: {}", dir.display(), e))?;
+    encode::encode(
+        dir,
+        Format::Mp4,
+        &Encoding::default().fps(timeline.fps).cleanup(),
+    )
+}
+
+/// Three sources at frequencies that all repeat every two seconds, so a loop of two seconds is
+/// seamless
+pub fn field() -> Field {
+    Field {
+        sources: vec![
+            Source::new(Vec2::new(-150.0, -100.0), 1.0, 0.0, 1.0),
+            Source::new(Vec2::new(150.0, -100.0), 1.0, PI, 1.0),
+            Source::new(Vec2::new(0.0, 160.0), 1.5, 0.0, 0.7),
+        ],
+        speed: 120.0,
+    }
+}
+
+/// Render a loop of the default field at 800 by 800 into the interference folder
+pub fn headless() -> Result<PathBuf, String> {
+    render(
+        &field(),
+        "interference",
+        Timeline::new(2.0, 30),
+        (800, 800),
+        Render::Heightfield,
+        palette(),
+    )
+}
+
+// Spacing of the grid the field is drawn with in the window
+const CELL: f32 = 8.0;
+// How close to a source a click has to be to pick it up
+const GRAB: f32 = 20.0;
+
+pub struct Model {
+    clock: Clock,
+    capture: Session,
+    pub field: Field,
+    pub render: Render,
+    pub palette: Palette,
+    /// Source being dragged
+    dragging: Option<usize>,
+}
+
+impl Model {
+    /// The field as it is on screen, for the next recording
+    fn run(&self) -> Run {
+        Run::new(
+            "interference",
+            Params::Interference {
+                field: self.field.clone(),
+                render: self.render,
+                palette: self.palette.clone(),
+            },
+        )
+    }
+}
+
+pub fn model(_app: &App) -> Model {
+    with_field(field(), Render::Heightfield, palette().clone())
+}
+
+/// The field, render and palette of an earlier run
+pub fn replay(run: &Run) -> Result<Model, String> {
+    match &run.params {
+        Params::Interference {
+            field,
+            render,
+            palette,
+        } => Ok(with_field(field.clone(), *render, palette.clone())),
+        _ => Err(format!("{} is not an interference run", run.sketch)),
+    }
+}
+
+fn with_field(field: Field, render: Render, palette: Palette) -> Model {
+    let mut model = Model {
+        clock: Clock::from_env(30),
+        capture: Session::new("interference").encode(Format::Mp4, Encoding::default()),
+        field,
+        render,
+        palette,
+        dragging: None,
+    };
+    model.capture.run = Some(model.run());
+    model
+}
+
+pub fn update(_app: &App, model: &mut Model, update: Update) {
+    model.clock.tick(&update);
+}
+
+/// Drag sources with the mouse, C switches between colours and contours and A adds a source
+/// under the mouse
+pub fn event(app: &App, model: &mut Model, event: Event) {
+    let mouse = app.mouse.position();
+    if let Event::WindowEvent {
+        simple: Some(event),
+        ..
+    } = event
+    {
+        match event {
+            WindowEvent::MousePressed(MouseButton::Left) => {
+                model.dragging = model.field.nearest(mouse, GRAB);
+            }
+            WindowEvent::MouseReleased(MouseButton::Left) => {
+                model.dragging = None;
+                model.capture.run = Some(model.run());
+            }
+            WindowEvent::MouseMoved(position) => {
+                if let Some(source) = model.dragging.and_then(|i| model.field.sources.get_mut(i)) {
+                    source.position = position;
+                }
+            }
+            WindowEvent::KeyPressed(Key::C) => {
+                model.render = model.render.next();
+                model.capture.run = Some(model.run());
+            }
+            WindowEvent::KeyPressed(Key::A) => {
+                model.field.sources.push(Source::new(mouse, 1.0, 0.0, 1.0));
+                model.capture.run = Some(model.run());
+            }
+            _ => (),
+        }
+    }
+}
+
+pub fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(BLACK);
+
+    let window = app.window_rect();
+    let t = model.clock.time();
+    let columns = (window.w() / CELL).ceil() as usize + 1;
+    let rows = (window.h() / CELL).ceil() as usize + 1;
+    let heights = model.field.sample(window, columns, rows, t);
+    let palette = &model.palette;
+    let range = model.field.peak() * 0.5;
+
+    match model.render {
+        Render::Heightfield => {
+            for j in 0..rows {
+                for i in 0..columns {
+                    let h = heights[j * columns + i];
+                    draw.rect()
+                        .xy(grid_point(window, columns, rows, i, j))
+                        .w_h(CELL, CELL)
+                        .color(palette.map(h, -range, range));
+                }
+            }
+        }
+        Render::Contours => {
+            for level in levels(model.field.peak()) {
+                let color = palette.map(level, -range, range);
+                for segment in contours(&heights, window, columns, rows, level) {
+                    draw.line()
+                        .start(segment.start())
+                        .end(segment.end())
+                        .weight(1.5)
+                        .color(color);
+                }
+            }
+        }
+    }
+
+    for (i, source) in model.field.sources.iter().enumerate() {
+        let color = if model.dragging == Some(i) {
+            SALMON
+        } else {
+            WHITE
+        };
+        draw.ellipse()
+            .xy(source.position)
+            .radius(6.0)
+            .no_fill()
+            .stroke_weight(2.0)
+            .stroke_color(color);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+    // Press R to start and stop recording
+    model.capture.capture(app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contours_of_a_slope_are_straight() {
+        // Heights rising from left to right, so the contour at 1.5 is the line x = 1.5
+        let (columns, rows) = (4, 3);
+        let heights = (0..rows)
+            .flat_map(|_| (0..columns).map(|i| i as f32))
+            .collect::<Vec<f32>>();
+        let rect = Rect::from_corners(Vec2::ZERO, Vec2::new(3.0, 2.0));
+        let segments = contours(&heights, rect, columns, rows, 1.5);
+        assert_eq!(segments.len(), 2);
+        for s in segments {
+            assert!((s.start().x - 1.5).abs() < 1e-6 && (s.end().x - 1.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn opposite_sources_cancel_between_them() {
+        let field = field();
+        let middle = Vec2::new(0.0, -100.0);
+        let pair = Field {
+            sources: field.sources[..2].to_vec(),
+            speed: field.speed,
+        };
+        for frame in 0..10 {
+            assert!(pair.height(middle, frame as f32 * 0.1).abs() < 1e-5);
+        }
+    }
+}
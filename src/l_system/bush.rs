@@ -1,23 +1,16 @@
 use std::collections::HashMap;
 
-use lindenmayer::LSystemStochastic;
-use nannou::{prelude::BLACK, App, Frame};
+use nannou::{color::rgba, prelude::BLACK, rand::random, App, Frame};
 
-use super::{cursor::Cursor, Action, SymbolReader};
+use super::{cursor::Cursor, recipe::Recipe, Action, SymbolReader};
+use crate::{palette::Palette, style::Style};
 
-use lazy_static::lazy_static;
+pub const AXIOM: &str = "X";
 
-lazy_static! {
-    static ref SYSTEM: LSystemStochastic = LSystemStochastic::new(
-        String::from("X"),
-        &[
-            ('X', vec![("F[X][+DX]-DX", 1.0)]),
-            ('F', vec![("L", 1.0), ("S", 1.0)]),
-        ],
-    );
-}
+pub const RULES: [(char, &str, f32); 3] =
+    [('X', "F[X][+DX]-DX", 1.0), ('F', "L", 1.0), ('F', "S", 1.0)];
 
-pub fn model(_app: &App) -> SymbolReader {
+pub fn recipe(seed: u64) -> Recipe {
     let actions = HashMap::from([
         ('F', Action::None),
         ('X', Action::None),
@@ -32,9 +25,16 @@ pub fn model(_app: &App) -> SymbolReader {
 
     let cursor = Cursor::new((0.0, 0.0), (0.0, 1.0));
 
-    let builder = SYSTEM.builder(4, None);
+    Recipe::stochastic(AXIOM, &RULES, 4, seed, actions, cursor)
+}
+
+/// Build the reader with an optional seed for the stochastic rules
+pub fn reader(seed: Option<u64>) -> SymbolReader {
+    recipe(seed.unwrap_or_else(random)).reader()
+}
 
-    SymbolReader::new(Box::new(builder), actions, cursor)
+pub fn model(_app: &App) -> SymbolReader {
+    reader(None)
 }
 
 pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
@@ -42,13 +42,7 @@ pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
 
     draw.background().color(BLACK);
 
-    for segment in model.segments.iter() {
-        segment
-            .line(&draw)
-            .rgba(0.5, 0.9, 0.266, 0.2)
-            .weight(2.0)
-            .caps_round();
-    }
+    Style::new(Palette::Solid(rgba(0.5, 0.9, 0.266, 0.2)), 2.0).draw_reader(&draw, model);
 
     for dot in model.positions.iter() {
         draw.ellipse()
@@ -59,10 +53,5 @@ pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
 
     draw.to_frame(app, &frame).unwrap();
 
-    // To create am mp4 from the images use the command below from the directory they are saved to
-    // ffmpeg -r 30 -f image2 -s 1920x1080 -i %04d.png -vcodec libx264 -crf 25  -pix_fmt yuv420p INSERT_NAME.mp4
-
-    // use crate::capture::captured_frame_path;
-    // let file_path = captured_frame_path(app, &frame, "hilbert_curve");
-    // app.main_window().capture_frame(file_path);
+    // To record the frames show the reader in a gallery::Gallery with a capture::Session
 }
@@ -1,20 +1,15 @@
 use std::collections::HashMap;
 
-use lindenmayer::LSystem;
-use nannou::{prelude::BLACK, App, Frame};
+use nannou::{color::rgba, prelude::BLACK, App, Frame};
 
-use super::{cursor::Cursor, Action, SymbolReader};
+use super::{cursor::Cursor, recipe::Recipe, Action, SymbolReader};
+use crate::{palette::Palette, style::Style};
 
-use lazy_static::lazy_static;
+pub const AXIOM: &str = "X";
 
-lazy_static! {
-    static ref SYSTEM: LSystem = LSystem::new(
-        String::from("X"),
-        &[('X', "X[-FFF][+FFF]FX"), ('Y', "YFX[+Y][-Y]"),],
-    );
-}
+pub const RULES: [(char, &str); 2] = [('X', "X[-FFF][+FFF]FX"), ('Y', "YFX[+Y][-Y]")];
 
-pub fn model(_app: &App) -> SymbolReader {
+pub fn recipe() -> Recipe {
     let actions = HashMap::from([
         ('X', Action::None),
         ('Y', Action::None),
@@ -27,9 +22,15 @@ pub fn model(_app: &App) -> SymbolReader {
 
     let cursor = Cursor::new((0.0, -500.0), (0.0, 1.0));
 
-    let builder = SYSTEM.builder(4);
+    Recipe::new(AXIOM, &RULES, 4, actions, cursor)
+}
 
-    SymbolReader::new(Box::new(builder), actions, cursor)
+pub fn reader() -> SymbolReader {
+    recipe().reader()
+}
+
+pub fn model(_app: &App) -> SymbolReader {
+    reader()
 }
 
 pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
@@ -37,20 +38,9 @@ pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
 
     draw.background().color(BLACK);
 
-    for segment in model.segments.iter() {
-        segment
-            .line(&draw)
-            .rgba(0.776, 0.811, 0.266, 0.5)
-            .weight(1.0)
-            .caps_round();
-    }
+    Style::new(Palette::Solid(rgba(0.776, 0.811, 0.266, 0.5)), 1.0).draw_reader(&draw, model);
 
     draw.to_frame(app, &frame).unwrap();
 
-    // To create am mp4 from the images use the command below from the directory they are saved to
-    // ffmpeg -r 30 -f image2 -s 1920x1080 -i %04d.png -vcodec libx264 -crf 25  -pix_fmt yuv420p INSERT_NAME.mp4
-
-    // use crate::capture::captured_frame_path;
-    // let file_path = captured_frame_path(app, &frame, "hilbert_curve");
-    // app.main_window().capture_frame(file_path);
+    // To record the frames show the reader in a gallery::Gallery with a capture::Session
 }
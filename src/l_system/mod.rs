@@ -6,13 +6,14 @@ pub mod hilbert;
 pub mod peano;
 pub mod peano_gosper;
 pub mod peano_variety;
+pub mod recipe;
 pub mod tree;
 
 use std::{collections::HashMap, time::Instant};
 
 use nannou::{glam::Vec2, prelude::Update, App};
 
-use crate::segment::Segment;
+use crate::segment::{bounds, Segment};
 
 use self::cursor::Cursor;
 
@@ -53,11 +54,86 @@ pub enum Action {
     PopAngle,
 }
 
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Action::None => write!(f, "None"),
+            Action::Unknown => write!(f, "Unknown"),
+            Action::Custom(name) => write!(f, "Custom({})", name),
+            Action::MoveForward(dist) => write!(f, "MoveForward({})", dist),
+            Action::DrawForward(dist) => write!(f, "DrawForward({})", dist),
+            Action::MoveTo(pos) => write!(f, "MoveTo({}, {})", pos.x, pos.y),
+            Action::DrawTo(pos) => write!(f, "DrawTo({}, {})", pos.x, pos.y),
+            Action::RotateRad(radians) => write!(f, "RotateRad({})", radians),
+            Action::RotateDeg(degrees) => write!(f, "RotateDeg({})", degrees),
+            Action::SetAngle(angle) => write!(f, "SetAngle({}, {})", angle.x, angle.y),
+            Action::PushCursor => write!(f, "PushCursor"),
+            Action::PopCursor => write!(f, "PopCursor"),
+            Action::PushPosition => write!(f, "PushPosition"),
+            Action::PopPosition => write!(f, "PopPosition"),
+            Action::PushAngle => write!(f, "PushAngle"),
+            Action::PopAngle => write!(f, "PopAngle"),
+        }
+    }
+}
+
+/// Parse an action written as by Display, such as "DrawForward(35)" or "MoveTo(1, 2)"
+impl std::str::FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, args) = match s.split_once('(') {
+            Some((name, rest)) => (
+                name.trim(),
+                rest.strip_suffix(')')
+                    .ok_or_else(|| format!("missing ) in action {}", s))?,
+            ),
+            None => (s, ""),
+        };
+        let numbers = || {
+            args.split(',')
+                .map(|n| n.trim().parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|e| format!("bad number in action {}: {}", s, e))
+        };
+        let one = || match numbers()?.as_slice() {
+            [n] => Ok(*n),
+            _ => Err(format!("action {} takes one number", s)),
+        };
+        let two = || match numbers()?.as_slice() {
+            [x, y] => Ok(Vec2::new(*x, *y)),
+            _ => Err(format!("action {} takes two numbers", s)),
+        };
+        Ok(match name {
+            "None" => Action::None,
+            "Unknown" => Action::Unknown,
+            "MoveForward" => Action::MoveForward(one()?),
+            "DrawForward" => Action::DrawForward(one()?),
+            "MoveTo" => Action::MoveTo(two()?),
+            "DrawTo" => Action::DrawTo(two()?),
+            "RotateRad" => Action::RotateRad(one()?),
+            "RotateDeg" => Action::RotateDeg(one()?),
+            "SetAngle" => Action::SetAngle(two()?),
+            "PushCursor" => Action::PushCursor,
+            "PopCursor" => Action::PopCursor,
+            "PushPosition" => Action::PushPosition,
+            "PopPosition" => Action::PopPosition,
+            "PushAngle" => Action::PushAngle,
+            "PopAngle" => Action::PopAngle,
+            // Custom actions name code in the sketch so can't be rebuilt from text
+            _ => return Err(format!("unknown action {}", s)),
+        })
+    }
+}
+
 /// Interpret a sequence of symbols as actions in 2D space.
 pub struct SymbolReader {
     expression: Box<dyn Iterator<Item = char>>,
     actions: HashMap<char, Action>,
     pub segments: Vec<Segment>,
+    /// Depth of the cursor stack when each segment was drawn
+    pub depths: Vec<usize>,
     pub cursors: Vec<Cursor>,
     pub positions: Vec<Vec2>,
     pub angles: Vec<Vec2>,
@@ -74,6 +150,7 @@ impl SymbolReader {
             expression,
             actions,
             segments: Vec::new(),
+            depths: Vec::new(),
             cursors: Vec::new(),
             positions: Vec::new(),
             angles: Vec::new(),
@@ -81,6 +158,17 @@ impl SymbolReader {
         }
     }
 
+    /// Step until a segment is drawn. Returns false once the expression has been read completely.
+    pub fn advance(&mut self) -> bool {
+        let drawn = self.segments.len();
+        while self.segments.len() == drawn {
+            if self.step().is_none() {
+                return false;
+            }
+        }
+        true
+    }
+
     /// Read the next character of the expression, perform the corresponding action, and then report the action
     /// Returns None if the expression has been read completely
     pub fn step(&mut self) -> Option<Action> {
@@ -92,12 +180,14 @@ impl SymbolReader {
                         self.cursor.forward(*dist);
                         self.segments
                             .push(Segment::from((old_pos, self.cursor.position())));
+                        self.depths.push(self.cursors.len());
                     }
                     Action::MoveForward(dist) => self.cursor.forward(*dist),
                     Action::DrawTo(pos) => {
                         let old_pos = self.cursor.position();
                         self.cursor.set_position(*pos);
                         self.segments.push(Segment::from((old_pos, *pos)));
+                        self.depths.push(self.cursors.len());
                     }
                     Action::MoveTo(pos) => self.cursor.set_position(*pos),
                     Action::RotateRad(radians) => self.cursor.rotate(*radians),
@@ -132,54 +222,26 @@ impl SymbolReader {
             None
         }
     }
+
+    /// Read the remainder of the expression, performing every action
+    pub fn read_all(&mut self) {
+        while self.step().is_some() {}
+    }
 }
 
 fn print_center(model: &mut SymbolReader) {
-    let x = {
-        let x_max = model
-            .segments
-            .iter()
-            .map(|s| s.center().x)
-            .reduce(f32::max)
-            .unwrap();
-        let x_min = model
-            .segments
-            .iter()
-            .map(|s| s.center().x)
-            .reduce(f32::min)
-            .unwrap();
-        (x_max + x_min) / 2.0
-    };
-    let y = {
-        let y_max = model
-            .segments
-            .iter()
-            .map(|s| s.center().y)
-            .reduce(f32::max)
-            .unwrap();
-        let y_min = model
-            .segments
-            .iter()
-            .map(|s| s.center().y)
-            .reduce(f32::min)
-            .unwrap();
-        (y_max + y_min) / 2.0
-    };
-    println!("center: ({x},{y})");
+    if let Some(rect) = bounds(&model.segments) {
+        println!("center: ({},{})", rect.x(), rect.y());
+    }
 }
 
 pub fn steps(app: &App, model: &mut SymbolReader, _update: Update) {
     if app.keys.down.contains(&nannou::prelude::Key::C) {
         print_center(model)
     }
-    loop {
-        if let Some(a) = model.step() {
-            match a {
-                // To save drawing time we break only when reaching an Action that changes the image
-                Action::DrawForward(_) => break,
-                _ => (),
-            }
-        } else {
+    while let Some(a) = model.step() {
+        // To save drawing time we break only when reaching an Action that changes the image
+        if let Action::DrawForward(_) = a {
             break;
         }
     }
@@ -190,14 +252,13 @@ pub fn steps_then_quit(app: &App, model: &mut SymbolReader, _update: Update) {
         print_center(model)
     }
     loop {
-        if let Some(a) = model.step() {
-            match a {
-                Action::DrawForward(_) => break,
-                _ => (),
+        match model.step() {
+            Some(Action::DrawForward(_)) => break,
+            Some(_) => (),
+            None => {
+                app.quit();
+                break;
             }
-        } else {
-            app.quit();
-            break;
         }
     }
 }
@@ -206,19 +267,11 @@ pub fn draw(app: &App, model: &mut SymbolReader, _update: Update) {
     if app.keys.down.contains(&nannou::prelude::Key::C) {
         print_center(model)
     }
-    loop {
-        if model.step().is_none() {
-            break;
-        }
-    }
+    model.read_all();
 }
 
 pub fn timed(_app: &App, model: &mut SymbolReader, _update: Update) {
     let t0 = Instant::now();
-    loop {
-        if model.step().is_none() {
-            break;
-        }
-    }
+    model.read_all();
     println!("{:?}", Instant::now() - t0);
 }
@@ -1,51 +1,198 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
-use lindenmayer::LSystem;
 use nannou::{
-    prelude::{BLACK, RED},
+    color::rgba,
+    prelude::{Vec2, BLACK, RED, WHITE},
     App, Frame,
 };
 
-use super::{cursor::Cursor, Action, SymbolReader};
+use super::{cursor::Cursor, recipe::Recipe, Action, SymbolReader};
+use crate::{
+    analysis::{analyze, CurveReport, Lattice},
+    palette::Palette,
+    run::{sidecar_path, Params, Run},
+    segment::{bounds, Segment},
+    style::Style,
+    svg,
+    transform::Transform,
+};
+
+// A-curve: ss+s+ss-s-ss (NW)
+// B-curve: ss-s-ss+s+ss (NE)
+
+// C-curve: +ss-s-ss+s+ss- (NW)
+// D-curve: -ss+s+ss-s-ss+ (NE)
+
+// In the replacement part of the rules A and C can be switched as can B and D
+
+pub const AXIOM: &str = "-A";
+
+pub const RULES: [(char, &str); 4] = [
+    ('A', "AsDsC+s+DsCsD-s-AsBsA"),
+    ('B', "DsCsB-s-AsBsA+s+BsAsB"),
+    ('C', "+BsAsD-s-AsBsA+s+BsAsB-"),
+    ('D', "-AsBsA+s+BsCsB-s-AsBsA+"),
+];
+
+const STEP: f32 = 15.0;
+
+/// One member of the family of curves made by switching A with C, and B with D, in the
+/// replacements. Bit 2i is set to switch A and C in the ith rule, bit 2i + 1 to switch B and D.
+///
+/// A switch applies to every occurrence in the rule at once, which gives 256 variants. Each
+/// occurrence could be switched on its own, but with 36 of them that is 2^36 curves, far too
+/// many to draw or browse. Nothing is lost in validity by the reduction: C draws the same cell
+/// as A by the other route, entering and leaving at the same points, as D does for B, so any
+/// switch of any occurrence still gives one unbroken curve.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Variant(pub u8);
 
-use lazy_static::lazy_static;
+impl Variant {
+    /// The rules exactly as written above
+    pub const ORIGINAL: Variant = Variant(0);
 
-lazy_static! {
-    // A-curve: ss+s+ss-s-ss (NW)
-    // B-curve: ss-s-ss+s+ss (NE)
+    /// Every combination of switches
+    pub fn all() -> impl Iterator<Item = Variant> {
+        (0..=u8::MAX).map(Variant)
+    }
+
+    fn swaps_ac(&self, rule: usize) -> bool {
+        self.0 & (1 << (2 * rule)) != 0
+    }
+
+    fn swaps_bd(&self, rule: usize) -> bool {
+        self.0 & (1 << (2 * rule + 1)) != 0
+    }
 
-    // C-curve: +ss-s-ss+s+ss- (NW)
-    // D-curve: -ss+s+ss-s-ss+ (NE)
+    pub fn rules(&self) -> Vec<(char, String)> {
+        RULES
+            .iter()
+            .enumerate()
+            .map(|(i, (symbol, replacement))| {
+                let replacement = replacement
+                    .chars()
+                    .map(|c| match c {
+                        'A' if self.swaps_ac(i) => 'C',
+                        'C' if self.swaps_ac(i) => 'A',
+                        'B' if self.swaps_bd(i) => 'D',
+                        'D' if self.swaps_bd(i) => 'B',
+                        _ => c,
+                    })
+                    .collect();
+                (*symbol, replacement)
+            })
+            .collect()
+    }
 
-    // In the replacement part of the rules A and C can be switched as can B and D
+    /// Short description listing which rules have switches, such as "A:AC D:AC,BD"
+    pub fn label(&self) -> String {
+        let parts = RULES
+            .iter()
+            .enumerate()
+            .filter_map(
+                |(i, (symbol, _))| match (self.swaps_ac(i), self.swaps_bd(i)) {
+                    (false, false) => None,
+                    (true, false) => Some(format!("{}:AC", symbol)),
+                    (false, true) => Some(format!("{}:BD", symbol)),
+                    (true, true) => Some(format!("{}:AC,BD", symbol)),
+                },
+            )
+            .collect::<Vec<String>>();
+        if parts.is_empty() {
+            String::from("original")
+        } else {
+            parts.join(" ")
+        }
+    }
 
-    static ref SYSTEM: LSystem = LSystem::new(
-        String::from("-A"),
-        &[
-            ('A', "AsDsC+s+DsCsD-s-AsBsA"),
-            ('B', "DsCsB-s-AsBsA+s+BsAsB"),
-            ('C', "+BsAsD-s-AsBsA+s+BsAsB-"),
-            ('D', "-AsBsA+s+BsCsB-s-AsBsA+"),
-        ],
-    );
+    pub fn recipe(&self, depth: usize) -> Recipe {
+        rules_recipe(&self.rules(), depth)
+    }
+
+    pub fn reader(&self, depth: usize) -> SymbolReader {
+        self.recipe(depth).reader()
+    }
+
+    /// All the segments of the curve at the given depth
+    pub fn segments(&self, depth: usize) -> Vec<Segment> {
+        let mut reader = self.reader(depth);
+        reader.read_all();
+        reader.segments
+    }
+
+    pub fn analyze(&self, depth: usize) -> CurveReport {
+        analyze(&self.segments(depth), Lattice::square(STEP))
+    }
 }
 
-pub fn model(_app: &App) -> SymbolReader {
-    let actions = HashMap::from([
+fn rules_recipe(rules: &[(char, String)], depth: usize) -> Recipe {
+    let rules = rules
+        .iter()
+        .map(|(c, r)| (*c, r.as_str()))
+        .collect::<Vec<(char, &str)>>();
+    Recipe::new(AXIOM, &rules, depth, actions(), cursor())
+}
+
+/// Every variant that draws a single unbroken curve which never meets itself, with its analysis
+pub fn valid_variants(depth: usize) -> Vec<(Variant, CurveReport)> {
+    Variant::all()
+        .map(|v| (v, v.analyze(depth)))
+        .filter(|(_, report)| report.is_simple_path())
+        .collect()
+}
+
+/// Write an SVG of every valid variant to the directory, returning how many were written
+pub fn export_variants(dir: impl AsRef<Path>, depth: usize) -> std::io::Result<usize> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let mut index = String::new();
+    let valid = valid_variants(depth);
+    for (variant, report) in valid.iter() {
+        let name = format!("peano_variety_{:03}", variant.0);
+        let path = dir.join(&name).with_extension("svg");
+        svg::save(
+            &path,
+            &[svg::Layer::new(&variant.segments(depth), "#c6cf44", 2.0)],
+            STEP,
+        )?;
+        Run::new(&name, Params::LSystem(variant.recipe(depth)))
+            .stamp()
+            .save(sidecar_path(&path))?;
+        index.push_str(&format!(
+            "{}\t{}\tcoverage {:.3}\n",
+            name,
+            variant.label(),
+            report.coverage.ratio()
+        ));
+    }
+    std::fs::write(dir.join("index.txt"), index)?;
+    Ok(valid.len())
+}
+
+// The rounded turn angles are the ones these curves have always been drawn with
+#[allow(clippy::approx_constant)]
+fn actions() -> HashMap<char, Action> {
+    HashMap::from([
         ('A', Action::None),
         ('B', Action::None),
         ('C', Action::None),
         ('D', Action::None),
-        ('s', Action::DrawForward(15.0)),
+        ('s', Action::DrawForward(STEP)),
         ('+', Action::RotateRad(1.5708)),
         ('-', Action::RotateRad(-1.5708)),
-    ]);
+    ])
+}
 
-    let cursor = Cursor::new((0.0, 0.0), (0.0, 1.0));
+fn cursor() -> Cursor {
+    Cursor::new((0.0, 0.0), (0.0, 1.0))
+}
 
-    let builder = SYSTEM.builder(4);
+pub fn reader() -> SymbolReader {
+    Variant::ORIGINAL.reader(4)
+}
 
-    SymbolReader::new(Box::new(builder), actions, cursor)
+pub fn model(_app: &App) -> SymbolReader {
+    reader()
 }
 
 pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
@@ -53,22 +200,131 @@ pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
 
     draw.background().color(BLACK);
 
-    for segment in model.segments.iter() {
-        segment
-            .line(&draw)
-            .rgba(0.776, 0.811, 0.266, 1.0)
-            .weight(1.0)
-            .caps_round();
-    }
+    Style::new(Palette::Solid(rgba(0.776, 0.811, 0.266, 1.0)), 1.0).draw_reader(&draw, model);
 
     draw.ellipse().radius(5.0).color(RED);
 
     draw.to_frame(app, &frame).unwrap();
 
-    // To create am mp4 from the images use the command below from the directory they are saved to
-    // ffmpeg -r 30 -f image2 -s 1920x1080 -i %04d.png -vcodec libx264 -crf 25  -pix_fmt yuv420p INSERT_NAME.mp4
+    // To record the frames show the reader in a gallery::Gallery with a capture::Session
+}
+
+/// The valid variants, each drawn ready to be laid out in a grid
+pub struct Family {
+    pub variants: Vec<(Variant, Vec<Segment>)>,
+}
+
+// Depth used when showing the whole family at once, below 3 most variants look the same
+const FAMILY_DEPTH: usize = 3;
 
-    // use crate::capture::captured_frame_path;
-    // let file_path = captured_frame_path(app, &frame, "hilbert_curve");
-    // app.main_window().capture_frame(file_path);
+pub fn family(_app: &App) -> Family {
+    Family {
+        variants: valid_variants(FAMILY_DEPTH)
+            .into_iter()
+            .map(|(v, _)| (v, v.segments(FAMILY_DEPTH)))
+            .collect(),
+    }
+}
+
+/// Draw every valid variant in a labelled grid filling the window
+pub fn family_view(app: &App, model: &Family, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(BLACK);
+
+    let window = app.window_rect();
+    let n = model.variants.len().max(1);
+    let columns = (n as f32).sqrt().ceil() as usize;
+    let rows = n.div_ceil(columns);
+    let (cell_w, cell_h) = (window.w() / columns as f32, window.h() / rows as f32);
+    let style = Style::new(Palette::Solid(rgba(0.776, 0.811, 0.266, 1.0)), 1.0);
+
+    for (i, (variant, segments)) in model.variants.iter().enumerate() {
+        let cell = Vec2::new(
+            window.left() + cell_w * ((i % columns) as f32 + 0.5),
+            window.top() - cell_h * ((i / columns) as f32 + 0.5),
+        );
+        if let Some(b) = bounds(segments) {
+            // Leave room at the bottom of the cell for the caption
+            let scale = (cell_w / b.w().max(1.0)).min(cell_h / b.h().max(1.0)) * 0.7;
+            let fit = Transform::translate(-Vec2::new(b.x(), b.y()))
+                .then(Transform::scale(scale))
+                .then(Transform::translate(cell + Vec2::new(0.0, cell_h * 0.07)));
+            style.draw(&draw, &fit.apply_segments(segments));
+        }
+        draw.text(&format!("{:03} {}", variant.0, variant.label()))
+            .xy(cell - Vec2::new(0.0, cell_h * 0.42))
+            .w(cell_w)
+            .font_size(10)
+            .color(WHITE);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn all_variants_are_distinct() {
+        let all = Variant::all().collect::<Vec<Variant>>();
+        assert_eq!(all.len(), 256);
+        assert_eq!(all[0], Variant::ORIGINAL);
+        let rules = all.iter().map(|v| v.rules()).collect::<HashSet<_>>();
+        assert_eq!(rules.len(), 256);
+    }
+
+    #[test]
+    fn variant_switches_whole_rules() {
+        assert_eq!(Variant::ORIGINAL.label(), "original");
+        let rules = Variant(0b1001).rules();
+        assert_eq!(rules[0], ('A', String::from("CsDsA+s+DsAsD-s-CsBsC")));
+        assert_eq!(rules[1], ('B', String::from("BsCsD-s-AsDsA+s+DsAsD")));
+        assert_eq!(rules[2].1, RULES[2].1);
+        assert_eq!(Variant(0b1001).label(), "A:AC B:BD");
+    }
+
+    #[test]
+    fn every_variant_is_valid() {
+        assert_eq!(valid_variants(2).len(), 256);
+    }
+
+    #[test]
+    fn switching_one_occurrence_is_valid() {
+        for (i, (_, replacement)) in RULES.iter().enumerate() {
+            for (j, c) in replacement.char_indices() {
+                let switched = match c {
+                    'A' => 'C',
+                    'C' => 'A',
+                    'B' => 'D',
+                    'D' => 'B',
+                    _ => continue,
+                };
+                let mut rules = Variant::ORIGINAL.rules();
+                rules[i].1.replace_range(j..j + 1, &switched.to_string());
+                let mut reader = rules_recipe(&rules, 2).reader();
+                reader.read_all();
+                let report = analyze(&reader.segments, Lattice::square(STEP));
+                assert!(report.is_simple_path(), "rule {} symbol {}", i, j);
+            }
+        }
+    }
+
+    #[test]
+    fn export_writes_every_valid_variant() {
+        let dir = std::env::temp_dir().join(format!("peano_variety_{}", std::process::id()));
+        let written = export_variants(&dir, 1).unwrap();
+        assert_eq!(written, 256);
+        let svgs = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().extension() == Some("svg".as_ref()))
+            .count();
+        assert_eq!(svgs, written);
+        let index = std::fs::read_to_string(dir.join("index.txt")).unwrap();
+        assert_eq!(index.lines().count(), written);
+        assert!(index.starts_with("peano_variety_000\toriginal\t"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
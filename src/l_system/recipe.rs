@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use lindenmayer::{LSystem, LSystemStochastic};
+
+use super::{cursor::Cursor, Action, SymbolReader};
+
+/// Everything needed to build a SymbolReader, so a run can be described and repeated
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    pub axiom: String,
+    /// Each replacement with its weight. Symbols with several replacements pick one at random
+    /// every time they are replaced, in proportion to the weights.
+    pub rules: Vec<(char, String, f32)>,
+    pub depth: usize,
+    /// Seed for the random choices, only used when a symbol has several replacements
+    pub seed: u64,
+    pub actions: HashMap<char, Action>,
+    pub cursor: Cursor,
+}
+
+impl Recipe {
+    pub fn new(
+        axiom: &str,
+        rules: &[(char, &str)],
+        depth: usize,
+        actions: HashMap<char, Action>,
+        cursor: Cursor,
+    ) -> Self {
+        Recipe {
+            axiom: String::from(axiom),
+            rules: rules
+                .iter()
+                .map(|(c, r)| (*c, String::from(*r), 1.0))
+                .collect(),
+            depth,
+            seed: 0,
+            actions,
+            cursor,
+        }
+    }
+
+    /// A recipe whose symbols may have several weighted replacements
+    pub fn stochastic(
+        axiom: &str,
+        rules: &[(char, &str, f32)],
+        depth: usize,
+        seed: u64,
+        actions: HashMap<char, Action>,
+        cursor: Cursor,
+    ) -> Self {
+        Recipe {
+            axiom: String::from(axiom),
+            rules: rules
+                .iter()
+                .map(|(c, r, w)| (*c, String::from(*r), *w))
+                .collect(),
+            depth,
+            seed,
+            actions,
+            cursor,
+        }
+    }
+
+    /// Apply the rules to the axiom depth times. Symbols without a rule are kept.
+    pub fn expression(&self) -> String {
+        let mut table: Vec<(char, Vec<(&str, f32)>)> = Vec::new();
+        for (c, replacement, weight) in self.rules.iter() {
+            match table.iter_mut().find(|(symbol, _)| symbol == c) {
+                Some((_, options)) => options.push((replacement.as_str(), *weight)),
+                None => table.push((*c, vec![(replacement.as_str(), *weight)])),
+            }
+        }
+        let axiom = self.axiom.clone();
+        if table.iter().all(|(_, options)| options.len() == 1) {
+            let rules = table
+                .iter()
+                .map(|(c, options)| (*c, options[0].0))
+                .collect::<Vec<(char, &str)>>();
+            return LSystem::new(axiom, &rules).string(self.depth);
+        }
+        if self.depth == 0 {
+            return axiom;
+        }
+        // The builder makes different choices than string for the same seed, and is what the
+        // sketches have always drawn
+        LSystemStochastic::new(axiom, &table)
+            .builder(self.depth, Some(self.seed))
+            .collect()
+    }
+
+    pub fn reader(&self) -> SymbolReader {
+        let expression = self.expression().chars().collect::<Vec<char>>();
+        SymbolReader::new(
+            Box::new(expression.into_iter()),
+            self.actions.clone(),
+            self.cursor,
+        )
+    }
+}
@@ -1,17 +1,15 @@
 use std::collections::HashMap;
 
-use lindenmayer::LSystem;
-use nannou::{prelude::BLACK, App, Frame};
+use nannou::{color::rgba, prelude::BLACK, App, Frame};
 
-use super::{cursor::Cursor, Action, SymbolReader};
+use super::{cursor::Cursor, recipe::Recipe, Action, SymbolReader};
+use crate::{palette::Palette, style::Style};
 
-use lazy_static::lazy_static;
+pub const AXIOM: &str = "X";
 
-lazy_static! {
-    static ref SYSTEM: LSystem = LSystem::new(String::from("X"), &[('X', "F[X][+FX]-FX")]);
-}
+pub const RULES: [(char, &str); 1] = [('X', "F[X][+FX]-FX")];
 
-pub fn model(_app: &App) -> SymbolReader {
+pub fn recipe() -> Recipe {
     let actions = HashMap::from([
         ('X', Action::None),
         ('D', Action::PushPosition),
@@ -24,9 +22,15 @@ pub fn model(_app: &App) -> SymbolReader {
 
     let cursor = Cursor::new((0.0, -200.0), (0.0, 1.0));
 
-    let builder = SYSTEM.builder(4);
+    Recipe::new(AXIOM, &RULES, 4, actions, cursor)
+}
+
+pub fn reader() -> SymbolReader {
+    recipe().reader()
+}
 
-    SymbolReader::new(Box::new(builder), actions, cursor)
+pub fn model(_app: &App) -> SymbolReader {
+    reader()
 }
 
 pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
@@ -34,13 +38,7 @@ pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
 
     draw.background().color(BLACK);
 
-    for segment in model.segments.iter() {
-        segment
-            .line(&draw)
-            .rgba(0.5, 0.9, 0.2, 0.2)
-            .weight(5.0)
-            .caps_round();
-    }
+    Style::new(Palette::Solid(rgba(0.5, 0.9, 0.2, 0.2)), 5.0).draw_reader(&draw, model);
 
     for dot in model.positions.iter() {
         draw.ellipse().xy(*dot).radius(3.0).rgba(0.9, 0.2, 0.2, 0.2);
@@ -48,10 +46,5 @@ pub fn view(app: &App, model: &SymbolReader, frame: Frame) {
 
     draw.to_frame(app, &frame).unwrap();
 
-    // To create am mp4 from the images use the command below from the directory they are saved to
-    // ffmpeg -r 30 -f image2 -s 1920x1080 -i %04d.png -vcodec libx264 -crf 25  -pix_fmt yuv420p tree.mp4
-
-    // use crate::capture::captured_frame_path;
-    // let file_path = captured_frame_path(app, &frame, "tree");
-    // app.main_window().capture_frame(file_path);
+    // To record the frames show the reader in a gallery::Gallery with a capture::Session
 }
@@ -1,12 +1,42 @@
+pub mod analysis;
+pub mod audio;
 pub mod capture;
+pub mod clock;
 pub mod dot;
+pub mod encode;
+pub mod epicycle;
+pub mod fourier;
+pub mod gallery;
+pub mod golden;
+pub mod harmonograph;
+pub mod interference;
 pub mod l_system;
 pub mod maze;
+pub mod palette;
+pub mod run;
 pub mod segment;
+pub mod spline;
+pub mod stroke;
+pub mod style;
+pub mod svg;
+pub mod symmetry;
+pub mod timeline;
+pub mod transform;
+pub mod tween;
 pub mod wave;
 
 fn main() {
-    //nannou::sketch(wave::view).run();
+    //nannou::app(wave::model).update(wave::update).simple_window(wave::view).run();
+    //nannou::app(|_| wave::live(wave::Waveform::Square, 5)).update(wave::update).event(wave::event).simple_window(wave::view).run();
+    //nannou::app(|_| wave::replay(&run::Run::load("wave/000/run.toml").unwrap()).unwrap()).update(wave::update).simple_window(wave::view).run();
+    //interference::headless().unwrap();
+    //maze::bench::run(&[16, 250, 1000]);
+    //nannou::app(maze::masked_maze::model).update(maze::update).simple_window(maze::view).run();
+    //maze::bench::compare(16, 20);
+    //nannou::app(maze::classic_maze::model).update(maze::update).simple_window(maze::view).run();
+    //nannou::app(maze::tiled_maze::model).update(maze::update).simple_window(maze::view).run();
+    //nannou::app(interference::model).update(interference::update).event(interference::event).simple_window(interference::view).run();
+    //nannou::app(fourier::model).update(fourier::update).event(fourier::event).simple_window(fourier::view).run();
     nannou::app(l_system::tree::model)
         .update(l_system::steps_then_quit)
         .simple_window(l_system::tree::view)
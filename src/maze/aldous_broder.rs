@@ -0,0 +1,55 @@
+use nannou::rand::{rngs::StdRng, seq::SliceRandom};
+
+use super::{
+    grid::{Cell, Grid},
+    Change, Generator,
+};
+
+/// The Aldous-Broder algorithm. Walks at random from cell to neighbour, carving whenever the walk
+/// steps into a cell it hasn't been to. Every maze is as likely as every other, but the walk
+/// takes a long time to find the last few cells.
+pub struct AldousBroder {
+    start: Option<Cell>,
+    cursor: Cell,
+    /// Cells joined to the start not yet reached
+    left: usize,
+}
+
+impl AldousBroder {
+    pub fn new(start: Cell) -> Self {
+        AldousBroder {
+            start: Some(start),
+            cursor: start,
+            left: 0,
+        }
+    }
+}
+
+impl Generator for AldousBroder {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            grid.visit(start);
+            self.cursor = start;
+            self.left = grid.region(start).len().saturating_sub(1);
+        }
+
+        while self.left > 0 {
+            let next = *grid.neighbors(self.cursor).choose(rng)?;
+            let from = std::mem::replace(&mut self.cursor, next);
+            if !grid.is_visited(next) {
+                grid.carve(from, next);
+                self.left -= 1;
+                return Some(Change::Carve(from, next));
+            }
+        }
+        None
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        Some(self.cursor)
+    }
+}
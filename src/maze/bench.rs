@@ -0,0 +1,66 @@
+use std::time::Instant;
+
+use nannou::rand::{rngs::StdRng, SeedableRng};
+
+use super::{generators, grid::Grid, stats::Stats, Generator};
+
+/// Run the generator until the maze is done
+fn generate(generator: &mut dyn Generator, grid: &mut Grid, seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    while generator.step(grid, &mut rng).is_some() {}
+}
+
+/// Generate a maze with each generator on square grids of each width without drawing anything,
+/// printing the time taken and the rate in cells per second. Build with --release for numbers
+/// worth comparing.
+pub fn run(widths: &[i32]) {
+    println!(
+        "{:>18} {:>12} {:>10} {:>14}",
+        "generator", "cells", "seconds", "cells/second"
+    );
+    for width in widths {
+        for (name, mut generator) in generators((*width, *width)) {
+            let mut grid = Grid::square(*width);
+            let start = Instant::now();
+            generate(generator.as_mut(), &mut grid, 0);
+            let seconds = start.elapsed().as_secs_f64();
+            println!(
+                "{:>18} {:>12} {:>10.3} {:>14.0}",
+                name,
+                grid.len(),
+                seconds,
+                grid.len() as f64 / seconds.max(f64::EPSILON)
+            );
+        }
+    }
+}
+
+/// Generate mazes with each generator on a square grid of the width, one for each seed, and
+/// print the mean of their statistics to compare the textures
+pub fn compare(width: i32, seeds: u64) {
+    let mut names = Vec::new();
+    let mut stats: Vec<Vec<Stats>> = Vec::new();
+    for seed in 0..seeds {
+        for (i, (name, mut generator)) in generators((width, width)).into_iter().enumerate() {
+            let mut grid = Grid::square(width);
+            generate(generator.as_mut(), &mut grid, seed);
+            if i == names.len() {
+                names.push(name);
+                stats.push(Vec::new());
+            }
+            stats[i].push(Stats::of(&grid));
+        }
+    }
+
+    println!(
+        "{:>18} {:>10} {:>10} {:>10} {:>10}",
+        "generator", "dead ends", "junctions", "straight", "longest"
+    );
+    for (name, stats) in names.iter().zip(stats) {
+        let mean = Stats::mean(&stats);
+        println!(
+            "{:>18} {:>10.3} {:>10.3} {:>10.3} {:>10.3}",
+            name, mean.dead_ends, mean.junctions, mean.straight, mean.longest
+        );
+    }
+}
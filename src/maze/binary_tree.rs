@@ -0,0 +1,72 @@
+use nannou::rand::{rngs::StdRng, seq::SliceRandom};
+
+use super::{
+    grid::{Cell, Grid},
+    sets::Sets,
+    Change, Generator,
+};
+
+/// The binary tree algorithm. Goes through the cells row by row, carving from each to a random
+/// neighbour above it or to its right. Needs no memory beyond the cell it's at, but leaves long
+/// straight corridors along the top and right edges and a strong diagonal bias.
+pub struct BinaryTree {
+    start: Option<Cell>,
+    /// Cells joined to the start, row by row from the bottom left
+    cells: Vec<Cell>,
+    next: usize,
+    sets: Sets,
+    /// Index of the cell being joined to its neighbours once every cell has been carved from
+    joined: usize,
+}
+
+impl BinaryTree {
+    pub fn new(start: Cell) -> Self {
+        BinaryTree {
+            start: Some(start),
+            cells: Vec::new(),
+            next: 0,
+            sets: Sets::new(),
+            joined: 0,
+        }
+    }
+}
+
+impl Generator for BinaryTree {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            self.cells = grid.region(start);
+            self.cells.sort_by_key(|c| (c.1, c.0));
+            self.next = 0;
+            self.sets = Sets::new();
+            self.joined = 0;
+        }
+
+        while let Some(&c) = self.cells.get(self.next) {
+            self.next += 1;
+            grid.visit(c);
+            let ahead: Vec<Cell> = grid
+                .neighbors(c)
+                .into_iter()
+                .filter(|n| (n.1, n.0) > (c.1, c.0))
+                .collect();
+            if let Some(&n) = ahead.choose(rng) {
+                if self.sets.union(c, n) {
+                    grid.carve(c, n);
+                    return Some(Change::Carve(c, n));
+                }
+            }
+        }
+
+        // Only the top right cell has nowhere to carve to in a rectangle, other shapes are left
+        // in pieces
+        self.sets.join(grid, &self.cells, &mut self.joined, rng)
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        self.cells.get(self.next).copied()
+    }
+}
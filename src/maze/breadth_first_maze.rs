@@ -1,127 +1,103 @@
-use itertools::{iproduct, Itertools};
 use nannou::{
     prelude::*,
-    rand::{rngs::ThreadRng, seq::SliceRandom, thread_rng},
+    rand::{random, rngs::StdRng, seq::SliceRandom, Rng},
 };
 
-use crate::segment::Segment;
+use super::{
+    grid::{Cell, Grid},
+    plan::Plan,
+    Change, Generator, Maze,
+};
+use crate::{
+    capture::Session,
+    encode::{Encoding, Format},
+    run::{Params, Run},
+};
 
-pub struct Model {
-    segments: Vec<Segment>,
-    active: Vec<(i32, i32)>,
-    cursor: (i32, i32),
-    scale: f32,
-    cells: Vec<(i32, i32)>,
+/// Grows the maze outwards from a set of active cells, carving from a random active cell to a
+/// random unvisited neighbour each step. Cells with no unvisited neighbours are dropped.
+pub struct BreadthFirst {
+    active: Vec<Cell>,
+    /// Index of the cell in active being carved from
+    cursor: usize,
 }
 
-impl Model {
-    fn outline(&self, draw: &Draw) {
-        let r_size = (WIDTH + 1) as f32 * self.scale * 2.0;
-        draw.rect()
-            .no_fill()
-            .w_h(r_size, r_size)
-            .stroke_color(BLACK)
-            .stroke_weight(3.0);
-    }
-
-    fn move_cursor_to_random(&mut self, rng: &mut ThreadRng) {
-        if let Some(cell) = self.active.choose(rng) {
-            self.cursor = *cell;
+impl BreadthFirst {
+    pub fn new(start: Cell) -> Self {
+        BreadthFirst {
+            active: vec![start],
+            cursor: 0,
         }
     }
 
-    pub fn neighbors_of(&self, p: (i32, i32)) -> Vec<(i32, i32)> {
-        [
-            (p.0 - 1, p.1),
-            (p.0 + 1, p.1),
-            (p.0, p.1 - 1),
-            (p.0, p.1 + 1),
-        ]
-        .iter()
-        .filter(|x| self.cells.contains(x))
-        .copied()
-        .collect_vec()
-    }
-
-    fn neighbors(&self) -> Vec<(i32, i32)> {
-        self.neighbors_of(self.cursor)
-    }
-}
-
-const WIDTH: i32 = 16;
-
-pub fn model(_app: &App) -> Model {
-    let cells = {
-        let xs = -WIDTH..=WIDTH;
-        let ys = -WIDTH..=WIDTH;
-        iproduct!(xs, ys).into_iter().collect_vec()
-    };
-
-    Model {
-        segments: Vec::new(),
-        active: vec![(0, 0)],
-        cursor: (0, 0),
-        scale: 20.0,
-        cells,
+    fn move_cursor_to_random(&mut self, rng: &mut StdRng) {
+        if !self.active.is_empty() {
+            self.cursor = rng.gen_range(0..self.active.len());
+        }
     }
 }
 
-pub fn update(app: &App, model: &mut Model, _update: Update) {
-    let mut rng = thread_rng();
-    let mut neighbors = model.neighbors();
+impl Generator for BreadthFirst {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        let mut cell = *self.active.get(self.cursor)?;
+        grid.visit(cell);
+        let mut neighbors = grid.unvisited_neighbors(cell);
+
+        // If there are no neighbors the cell is dead so remove it and move to a random active cell
+        while neighbors.is_empty() {
+            // Order doesn't matter as cells are picked at random, so removal is constant time
+            self.active.swap_remove(self.cursor);
+            if self.active.is_empty() {
+                return None;
+            }
+            self.move_cursor_to_random(rng);
+            cell = self.active[self.cursor];
+            neighbors = grid.unvisited_neighbors(cell);
+        }
 
-    if model.active.is_empty() {
-        app.quit()
-    }
+        // If there are neighbors pick one and carve to it, marking it active
+        let endpoint = *neighbors.choose(rng).unwrap();
+        grid.carve(cell, endpoint);
+        self.active.push(endpoint);
 
-    // If there are no neighbors the cell is dead so remove it and move to a random active cell
-    while neighbors.is_empty() {
-        model.active.retain(|x| x != &model.cursor);
-        if model.active.is_empty() {
-            break;
-        }
         // Move the cursor to a random active position
-        model.move_cursor_to_random(&mut rng);
-        neighbors = model.neighbors();
+        self.move_cursor_to_random(rng);
+        Some(Change::Carve(cell, endpoint))
     }
 
-    if model.active.is_empty() {
-        app.quit();
-    } else {
-        // If there are neighbors pick one and draw the line
-        let endpoint = *neighbors.choose(&mut rng).unwrap();
-        model
-            .segments
-            .push(Segment::from((model.cursor, endpoint)).scaled(model.scale));
-
-        // Mark the endpoint as active and remove it from cells
-        model.active.push(endpoint);
-        model.cells.retain(|x| x != &endpoint);
+    fn start(&mut self, cell: Cell) {
+        self.active = vec![cell];
+        self.cursor = 0;
+    }
 
-        // Move the cursor to a random active position
-        model.move_cursor_to_random(&mut rng);
+    fn cursor(&self) -> Option<Cell> {
+        self.active.get(self.cursor).copied()
     }
 }
 
-pub fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
+pub const WIDTH: i32 = 16;
+pub const SCALE: f32 = 20.0;
 
-    draw.background().color(BLACK);
-
-    for segment in model.segments.iter() {
-        segment
-            .line(&draw)
-            .color(PALEGOLDENROD)
-            .weight(10.0)
-            .caps_round();
-    }
-
-    draw.to_frame(app, &frame).unwrap();
+pub fn model(_app: &App) -> Maze {
+    seeded(random())
+}
 
-    // To create am mp4 from the images use the command below from the directory they are saved to
-    // ffmpeg -r 30 -f image2 -s 1920x1080 -i %04d.png -vcodec libx264 -crf 25  -pix_fmt yuv420p breadth_first_maze.mp4
+/// What decides the passages of the maze for the seed
+pub fn plan(seed: u64) -> Plan {
+    Plan::square(seed, "breadth_first", WIDTH, SCALE)
+}
 
-    // use crate::capture::captured_frame_path;
-    // let file_path = captured_frame_path(app, &frame, "breadth_first_maze");
-    // app.main_window().capture_frame(file_path);
+/// Create a model whose choices are fully determined by the seed
+pub fn seeded(seed: u64) -> Maze {
+    Maze::new(
+        Grid::square(WIDTH),
+        BreadthFirst::new((WIDTH, WIDTH)),
+        seed,
+        SCALE,
+    )
+    .capture(
+        Session::new("breadth_first_maze")
+            .run(Run::new("breadth_first_maze", Params::Maze(plan(seed))))
+            .encode(Format::Mp4, Encoding::default()),
+    )
 }
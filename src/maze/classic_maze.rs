@@ -0,0 +1,45 @@
+use nannou::{
+    prelude::*,
+    rand::{random, seq::SliceRandom, thread_rng},
+};
+
+use super::{plan::Plan, Maze};
+use crate::{
+    capture::Session,
+    encode::{Encoding, Format},
+    run::{Params, Run},
+};
+
+pub const WIDTH: i32 = 16;
+pub const SCALE: f32 = 20.0;
+
+/// The classic algorithms, by the names they go by in `generators`
+pub const ALGORITHMS: [&str; 9] = [
+    "kruskal",
+    "prim",
+    "wilson",
+    "aldous_broder",
+    "eller",
+    "recursive_division",
+    "sidewinder",
+    "binary_tree",
+    "hunt_and_kill",
+];
+
+/// The algorithm named by the MAZE environment variable, or one at random
+pub fn model(_app: &App) -> Maze {
+    let name = std::env::var("MAZE")
+        .unwrap_or_else(|_| ALGORITHMS.choose(&mut thread_rng()).unwrap().to_string());
+    seeded(&name, random()).unwrap()
+}
+
+/// A maze made by the named algorithm whose choices are fully determined by the seed
+pub fn seeded(name: &str, seed: u64) -> Result<Maze, String> {
+    let plan = Plan::square(seed, name, WIDTH, SCALE);
+    let sketch = format!("{}_maze", name);
+    Ok(plan.maze()?.capture(
+        Session::new(&sketch)
+            .run(Run::new(&sketch, Params::Maze(plan)))
+            .encode(Format::Mp4, Encoding::default()),
+    ))
+}
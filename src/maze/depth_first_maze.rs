@@ -1,124 +1,98 @@
-use itertools::{iproduct, Itertools};
 use nannou::{
     prelude::*,
-    rand::{seq::SliceRandom, thread_rng},
+    rand::{random, rngs::StdRng, seq::SliceRandom},
 };
 
-use crate::segment::Segment;
+use super::{
+    grid::{Cell, Grid},
+    plan::Plan,
+    Change, Generator, Maze,
+};
+use crate::{
+    capture::Session,
+    encode::{Encoding, Format},
+    palette::{named_color, Palette},
+    run::{Params, Run},
+    style::Style,
+};
 
-pub struct Model {
-    segments: Vec<Segment>,
-    finished_segments: Vec<Segment>,
-    stack: Vec<(i32, i32)>,
-    cursor: (i32, i32),
-    scale: f32,
-    cells: Vec<(i32, i32)>,
+/// Randomized depth first search. Carves from the cursor to a random unvisited neighbour,
+/// backtracking along the path when there are none.
+pub struct DepthFirst {
+    stack: Vec<Cell>,
+    cursor: Cell,
 }
 
-impl Model {
-    fn outline(&self, draw: &Draw) {
-        let r_size = (WIDTH + 1) as f32 * self.scale * 2.0;
-        draw.rect()
-            .no_fill()
-            .w_h(r_size, r_size)
-            .stroke_color(BLACK)
-            .stroke_weight(3.0);
+impl DepthFirst {
+    pub fn new(start: Cell) -> Self {
+        DepthFirst {
+            stack: Vec::new(),
+            cursor: start,
+        }
     }
+}
 
-    pub fn neighbors_of(&self, p: (i32, i32)) -> Vec<(i32, i32)> {
-        [
-            (p.0 - 1, p.1),
-            (p.0 + 1, p.1),
-            (p.0, p.1 - 1),
-            (p.0, p.1 + 1),
-        ]
-        .iter()
-        .filter(|x| self.cells.contains(x))
-        .copied()
-        .collect_vec()
+impl Generator for DepthFirst {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        grid.visit(self.cursor);
+        let neighbors = grid.unvisited_neighbors(self.cursor);
+        match neighbors.choose(rng) {
+            // If there are neighbors pick one, move to cursor there
+            Some(next) => {
+                grid.carve(self.cursor, *next);
+                self.stack.push(self.cursor);
+                let from = std::mem::replace(&mut self.cursor, *next);
+                Some(Change::Carve(from, *next))
+            }
+            // If there are no neighbors backtrack
+            None => {
+                let back = self.stack.pop()?;
+                let from = std::mem::replace(&mut self.cursor, back);
+                Some(Change::Settle(back, from))
+            }
+        }
     }
 
-    fn neighbors(&self) -> Vec<(i32, i32)> {
-        self.neighbors_of(self.cursor)
+    fn start(&mut self, cell: Cell) {
+        self.stack.clear();
+        self.cursor = cell;
     }
-}
-
-const WIDTH: i32 = 16;
 
-pub fn model(_app: &App) -> Model {
-    let cells = {
-        let xs = -WIDTH..=WIDTH;
-        let ys = -WIDTH..=WIDTH;
-        iproduct!(xs, ys).into_iter().collect_vec()
-    };
-
-    Model {
-        segments: Vec::new(),
-        finished_segments: Vec::new(),
-        stack: Vec::new(),
-        cursor: (0, 0),
-        scale: 20.0,
-        cells,
+    fn cursor(&self) -> Option<Cell> {
+        Some(self.cursor)
     }
 }
 
-pub fn update(app: &App, model: &mut Model, _update: Update) {
-    // use std::{thread, time};
-
-    // let t = time::Duration::from_millis(50);
-    // thread::sleep(t);
+pub const WIDTH: i32 = 16;
+pub const SCALE: f32 = 20.0;
 
-    if model.cells.is_empty() {
-        if model.segments.is_empty() {
-            app.quit();
-        } else {
-            model.finished_segments.push(model.segments.pop().unwrap());
-        }
-    } else {
-        let mut rng = thread_rng();
-        let neighbors = model.neighbors();
-        // If there are no neighbors backtrack
-        if neighbors.is_empty() {
-            model.cursor = model.stack.pop().unwrap();
-            model.finished_segments.push(model.segments.pop().unwrap());
-        // If there are neighbors pick one, move to cursor there
-        } else {
-            model.stack.push(model.cursor);
-            let new_pos = *neighbors.choose(&mut rng).unwrap();
-            model.cells.retain(|x| x != &new_pos);
-            model
-                .segments
-                .push(Segment::from((model.cursor, new_pos)).scaled(model.scale));
-            model.cursor = new_pos;
-        }
-    }
+pub fn model(_app: &App) -> Maze {
+    seeded(random())
 }
 
-pub fn view(app: &App, model: &Model, frame: Frame) {
-    let draw = app.draw();
-    //draw.background().color(BLACK);
-    for segment in model.segments.iter() {
-        segment
-            .line(&draw)
-            .color(SLATEBLUE)
-            .weight(2.0)
-            .caps_round();
-    }
-    for segment in model.finished_segments.iter() {
-        segment
-            .line(&draw)
-            .color(PALEGOLDENROD)
-            .weight(10.0)
-            .caps_round();
-    }
-
-    model.outline(&draw);
-    draw.to_frame(app, &frame).unwrap();
-
-    // To create am mp4 from the images use the command below from the directory they are saved to
-    // ffmpeg -r 30 -f image2 -s 1920x1080 -i %04d.png -vcodec libx264 -crf 25  -pix_fmt yuv420p depth_first_maze.mp4
+/// What decides the passages of the maze for the seed
+pub fn plan(seed: u64) -> Plan {
+    Plan::square(seed, "depth_first", WIDTH, SCALE)
+}
 
-    // use crate::capture::captured_frame_path;
-    // let file_path = captured_frame_path(app, &frame, "depth_first_maze");
-    // app.main_window().capture_frame(file_path);
+/// Create a model whose choices are fully determined by the seed
+pub fn seeded(seed: u64) -> Maze {
+    Maze::new(
+        Grid::square(WIDTH),
+        DepthFirst::new((WIDTH, WIDTH)),
+        seed,
+        SCALE,
+    )
+    // The path still being carved thin, finished passages thick
+    .styles(
+        Style::new(Palette::Solid(named_color(SLATEBLUE, 1.0)), 2.0),
+        Style::new(Palette::Solid(named_color(PALEGOLDENROD, 1.0)), 10.0),
+    )
+    .background(None)
+    .outline()
+    .capture(
+        Session::new("depth_first_maze")
+            .run(Run::new("depth_first_maze", Params::Maze(plan(seed))))
+            .encode(Format::Mp4, Encoding::default()),
+    )
 }
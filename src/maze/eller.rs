@@ -0,0 +1,112 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use nannou::rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use super::{
+    grid::{Cell, Grid},
+    sets::Sets,
+    Change, Generator,
+};
+
+/// Eller's algorithm. Works a row at a time, keeping track of which cells of the row are already
+/// joined through the rows below. Neighbours along the row not yet joined are carved between at
+/// random, then each group carves up at least once so none is cut off. The top row joins
+/// everything left apart.
+pub struct Eller {
+    start: Option<Cell>,
+    /// Cells joined to the start, row by row from the bottom left
+    cells: Vec<Cell>,
+    /// Index of the first cell of the next row
+    next: usize,
+    /// Passages chosen for the latest row, to carve
+    passages: VecDeque<(Cell, Cell)>,
+    sets: Sets,
+    /// Index of the cell being joined to its neighbours once every row is done
+    joined: usize,
+}
+
+impl Eller {
+    pub fn new(start: Cell) -> Self {
+        Eller {
+            start: Some(start),
+            cells: Vec::new(),
+            next: 0,
+            passages: VecDeque::new(),
+            sets: Sets::new(),
+            joined: 0,
+        }
+    }
+
+    /// Choose the passages along the next row and up from it
+    fn row(&mut self, grid: &mut Grid, rng: &mut StdRng) {
+        let y = self.cells[self.next].1;
+        let end = self.cells[self.next..]
+            .iter()
+            .position(|c| c.1 != y)
+            .map_or(self.cells.len(), |i| self.next + i);
+        let row = self.cells[self.next..end].to_vec();
+        self.next = end;
+        let last = end == self.cells.len();
+
+        for &c in &row {
+            grid.visit(c);
+            let right = grid
+                .neighbors(c)
+                .into_iter()
+                .find(|n| n.1 == c.1 && n.0 > c.0);
+            if let Some(right) = right {
+                if (last || rng.gen()) && self.sets.union(c, right) {
+                    self.passages.push_back((c, right));
+                }
+            }
+        }
+
+        // Every group needs a way up, and any more are left to chance
+        let groups: HashMap<Cell, Cell> = row.iter().map(|c| (*c, self.sets.find(*c))).collect();
+        let mut up: Vec<(Cell, Cell)> = row
+            .iter()
+            .flat_map(|a| grid.neighbors(*a).into_iter().map(move |b| (*a, b)))
+            .filter(|(a, b)| b.1 > a.1)
+            .collect();
+        up.shuffle(rng);
+        let mut reached = HashSet::new();
+        for (a, b) in up {
+            let first = reached.insert(groups[&a]);
+            if (first || rng.gen()) && self.sets.union(a, b) {
+                self.passages.push_back((a, b));
+            }
+        }
+    }
+}
+
+impl Generator for Eller {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            self.cells = grid.region(start);
+            self.cells.sort_by_key(|c| (c.1, c.0));
+            self.next = 0;
+            self.passages.clear();
+            self.sets = Sets::new();
+            self.joined = 0;
+        }
+
+        while self.passages.is_empty() && self.next < self.cells.len() {
+            self.row(grid, rng);
+        }
+        if let Some((a, b)) = self.passages.pop_front() {
+            grid.carve(a, b);
+            return Some(Change::Carve(a, b));
+        }
+
+        // A rectangle is joined up by now, other shapes may be left in pieces
+        self.sets.join(grid, &self.cells, &mut self.joined, rng)
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        self.passages.front().map(|(a, _)| *a)
+    }
+}
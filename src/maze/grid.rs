@@ -0,0 +1,299 @@
+use nannou::{geom::Rect, prelude::Vec2};
+
+use super::{mask::Mask, topology::Topology};
+use crate::segment::Segment;
+
+/// Position of a cell in the rectangle its grid's topology lays out, from (0, 0) at the bottom
+/// left
+pub type Cell = (i32, i32);
+
+/// A fixed number of flags in one bit each
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Bits(Vec<u64>);
+
+impl Bits {
+    pub(super) fn new(len: usize) -> Self {
+        Bits(vec![0; len.div_ceil(64)])
+    }
+
+    pub(super) fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub(super) fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+}
+
+/// The cells a maze is carved through, which of them have been reached and the passages
+/// opened between them.
+///
+/// Cells are kept in flat arrays indexed by position, so every lookup and update takes the same
+/// time however large the grid is and generating a maze is linear in the number of cells. Cells
+/// left out by the mask are outside the maze and never carved to. The topology decides which
+/// cells are neighbours and where each is drawn.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    pub columns: usize,
+    pub rows: usize,
+    topology: Topology,
+    mask: Mask,
+    /// Middle and size of the rectangle the cells fill
+    origin: Vec2,
+    size: Vec2,
+    len: usize,
+    /// One bit per cell, set once the cell has been reached
+    visited: Bits,
+    remaining: usize,
+    /// Open sides of each cell, a bit for each side in the order the topology gives them
+    open: Vec<u16>,
+    passages: Vec<(Cell, Cell)>,
+    // Index before which every cell has been visited, so finding the next is linear overall
+    scan: usize,
+}
+
+impl Grid {
+    /// Every cell of a rectangle
+    pub fn new(columns: usize, rows: usize) -> Self {
+        Grid::masked(Mask::rect(columns, rows))
+    }
+
+    /// A square of 2 * width + 1 cells a side, with the middle cell at (width, width)
+    pub fn square(width: i32) -> Self {
+        let side = (width.max(0) * 2 + 1) as usize;
+        Grid::new(side, side)
+    }
+
+    /// The squares of the mask's rectangle that are in the mask
+    pub fn masked(mask: Mask) -> Self {
+        Grid::tiled(Topology::Square, mask)
+    }
+
+    /// Hexagons in rows shifted back and forth by half a cell
+    pub fn hex(columns: usize, rows: usize) -> Self {
+        Grid::tiled(Topology::Hex, Mask::rect(columns, rows))
+    }
+
+    /// Triangles pointing up and down in turn, two to a unit across
+    pub fn triangle(columns: usize, rows: usize) -> Self {
+        Grid::tiled(Topology::Triangle, Mask::rect(columns, rows))
+    }
+
+    /// Octagons with squares between them, the number of octagons across and up
+    pub fn octagon(columns: usize, rows: usize) -> Self {
+        let side = |n: usize| (n * 2).saturating_sub(1);
+        Grid::tiled(Topology::Octagon, Mask::rect(side(columns), side(rows)))
+    }
+
+    /// Rings of cells around a middle cell, the rings a unit wide
+    pub fn polar(rings: usize) -> Self {
+        let topology = Topology::polar(rings);
+        let columns = match &topology {
+            Topology::Polar { counts, .. } => counts[rings.saturating_sub(1)],
+            _ => 1,
+        };
+        Grid::tiled(topology, Mask::rect(columns, rings))
+    }
+
+    /// The cells of the mask's rectangle laid out by the topology that are in the mask
+    pub fn tiled(topology: Topology, mask: Mask) -> Self {
+        let (columns, rows) = (mask.columns, mask.rows);
+        let mask = Mask::from_fn(columns, rows, |c| mask.contains(c) && topology.contains(c));
+        let len = mask.len();
+        let mut grid = Grid {
+            columns,
+            rows,
+            topology,
+            mask,
+            origin: Vec2::ZERO,
+            size: Vec2::ZERO,
+            len,
+            visited: Bits::new(columns * rows),
+            remaining: len,
+            open: vec![0; columns * rows],
+            passages: Vec::new(),
+            scan: 0,
+        };
+
+        let corners = grid.cells().flat_map(|c| {
+            let sides = grid.topology.sides(c);
+            sides.into_iter().flat_map(|(_, a, b)| [a, b])
+        });
+        let (low, high) = corners.fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(low, high), p| (low.min(p), high.max(p)),
+        );
+        if low.x <= high.x {
+            grid.origin = (low + high) / 2.0;
+            grid.size = high - low;
+        }
+        grid
+    }
+
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    pub fn contains(&self, c: Cell) -> bool {
+        self.mask.contains(c)
+    }
+
+    /// Position of the cell in the flat arrays, row by row from the bottom left
+    fn index(&self, c: Cell) -> usize {
+        c.1 as usize * self.columns + c.0 as usize
+    }
+
+    fn cell(&self, i: usize) -> Cell {
+        ((i % self.columns) as i32, (i / self.columns) as i32)
+    }
+
+    /// Number of cells in the maze
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Every cell, row by row from the bottom left
+    pub fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        (0..self.columns * self.rows)
+            .map(|i| self.cell(i))
+            .filter(|c| self.contains(*c))
+    }
+
+    /// The cell nearest the middle of the grid, somewhere to start from
+    pub fn middle(&self) -> Option<Cell> {
+        self.cells().min_by(|a, b| {
+            let distance = |c: &Cell| self.center(*c).length_squared();
+            distance(a).total_cmp(&distance(b))
+        })
+    }
+
+    /// Cells next to the cell in the grid, in the order of the sides of the cell
+    pub fn neighbors(&self, c: Cell) -> Vec<Cell> {
+        self.topology
+            .neighbors(c)
+            .into_iter()
+            .filter(|n| self.contains(*n))
+            .collect()
+    }
+
+    /// Cells joined to the start through neighbours, nearest first. Every cell after the start
+    /// has a neighbour before it.
+    pub fn region(&self, start: Cell) -> Vec<Cell> {
+        if !self.contains(start) {
+            return Vec::new();
+        }
+        let mut seen = Bits::new(self.columns * self.rows);
+        seen.set(self.index(start));
+        let mut cells = vec![start];
+        let mut i = 0;
+        while i < cells.len() {
+            for n in self.neighbors(cells[i]) {
+                if !seen.get(self.index(n)) {
+                    seen.set(self.index(n));
+                    cells.push(n);
+                }
+            }
+            i += 1;
+        }
+        cells
+    }
+
+    pub fn unvisited_neighbors(&self, c: Cell) -> Vec<Cell> {
+        self.neighbors(c)
+            .into_iter()
+            .filter(|n| !self.is_visited(*n))
+            .collect()
+    }
+
+    pub fn is_visited(&self, c: Cell) -> bool {
+        self.contains(c) && self.visited.get(self.index(c))
+    }
+
+    pub fn visit(&mut self, c: Cell) {
+        if self.contains(c) && !self.is_visited(c) {
+            let i = self.index(c);
+            self.visited.set(i);
+            self.remaining -= 1;
+        }
+    }
+
+    /// Cells not yet reached
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// The first cell not yet reached, for carving parts of a mask cut off from the rest
+    pub fn next_unvisited(&mut self) -> Option<Cell> {
+        while self.scan < self.columns * self.rows {
+            let c = self.cell(self.scan);
+            if self.contains(c) && !self.is_visited(c) {
+                return Some(c);
+            }
+            self.scan += 1;
+        }
+        None
+    }
+
+    /// The bit for the side of the first cell the second is across, if they're neighbours
+    fn side(&self, a: Cell, b: Cell) -> Option<u16> {
+        if !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+        let side = self.topology.neighbors(a).iter().position(|n| *n == b)?;
+        Some(1 << side)
+    }
+
+    /// Open a passage between neighbouring cells, visiting both
+    pub fn carve(&mut self, a: Cell, b: Cell) {
+        self.visit(a);
+        self.visit(b);
+        if let (Some(from), Some(to)) = (self.side(a, b), self.side(b, a)) {
+            let (i, j) = (self.index(a), self.index(b));
+            self.open[i] |= from;
+            self.open[j] |= to;
+        }
+        self.passages.push((a, b));
+    }
+
+    /// True if there is a passage between the cells
+    pub fn is_open(&self, a: Cell, b: Cell) -> bool {
+        self.side(a, b)
+            .is_some_and(|side| self.open[self.index(a)] & side != 0)
+    }
+
+    /// Passages in the order they were carved
+    pub fn passages(&self) -> &[(Cell, Cell)] {
+        &self.passages
+    }
+
+    /// Middle of the cell with the grid centered on the origin, about one unit per cell
+    pub fn center(&self, c: Cell) -> Vec2 {
+        self.topology.center(c) - self.origin
+    }
+
+    /// The rectangle the grid fills at the scale, with half a cell's margin all round for the
+    /// outline
+    pub fn area(&self, scale: f32) -> Rect {
+        let size = (self.size + Vec2::ONE) * scale;
+        Rect::from_w_h(size.x, size.y)
+    }
+
+    /// Walls around the edge of the maze, along the sides of the cells at the edge of the mask
+    /// with no cell across them
+    pub fn outline(&self, scale: f32) -> Vec<Segment> {
+        let mut walls = Vec::new();
+        for c in self.cells() {
+            for (n, a, b) in self.topology.sides(c) {
+                if !self.contains(n) {
+                    let (a, b) = (a - self.origin, b - self.origin);
+                    walls.push(Segment::from((a * scale, b * scale)));
+                }
+            }
+        }
+        walls
+    }
+}
@@ -0,0 +1,71 @@
+use nannou::rand::{rngs::StdRng, seq::SliceRandom};
+
+use super::{
+    grid::{Cell, Grid},
+    Change, Generator,
+};
+
+/// Hunt-and-kill. Carves a random walk through unvisited cells like depth first search, but when
+/// the walk is stuck it hunts for the first unvisited cell next to the maze instead of
+/// backtracking, and carries on from there.
+pub struct HuntAndKill {
+    start: Option<Cell>,
+    cursor: Cell,
+    /// Cells joined to the start, nearest first, in the order they're hunted through. Each has a
+    /// neighbour before it, so the first unvisited one is always next to the maze.
+    cells: Vec<Cell>,
+    /// Index before which every cell has been visited
+    hunt: usize,
+}
+
+impl HuntAndKill {
+    pub fn new(start: Cell) -> Self {
+        HuntAndKill {
+            start: Some(start),
+            cursor: start,
+            cells: Vec::new(),
+            hunt: 0,
+        }
+    }
+}
+
+impl Generator for HuntAndKill {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            grid.visit(start);
+            self.cursor = start;
+            self.cells = grid.region(start);
+            self.hunt = 0;
+        }
+
+        // Kill, walking on from the cursor
+        if let Some(&next) = grid.unvisited_neighbors(self.cursor).choose(rng) {
+            grid.carve(self.cursor, next);
+            let from = std::mem::replace(&mut self.cursor, next);
+            return Some(Change::Carve(from, next));
+        }
+
+        // Hunt, joining the first unvisited cell to a random neighbour in the maze
+        while grid.is_visited(*self.cells.get(self.hunt)?) {
+            self.hunt += 1;
+        }
+        let found = self.cells[self.hunt];
+        let neighbors: Vec<Cell> = grid
+            .neighbors(found)
+            .into_iter()
+            .filter(|n| grid.is_visited(*n))
+            .collect();
+        let from = *neighbors.choose(rng)?;
+        grid.carve(from, found);
+        self.cursor = found;
+        Some(Change::Carve(from, found))
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        Some(self.cursor)
+    }
+}
@@ -0,0 +1,61 @@
+use nannou::rand::{rngs::StdRng, seq::SliceRandom};
+
+use super::{
+    grid::{Cell, Grid},
+    sets::Sets,
+    Change, Generator,
+};
+
+/// Randomized Kruskal's algorithm. Goes through the walls between neighbouring cells in a random
+/// order, carving through each one that joins two groups of cells not yet joined, so passages
+/// appear all over the maze at once and merge.
+pub struct Kruskal {
+    start: Option<Cell>,
+    /// Walls not yet looked at, taken from the end
+    walls: Vec<(Cell, Cell)>,
+    sets: Sets,
+    cursor: Option<Cell>,
+}
+
+impl Kruskal {
+    pub fn new(start: Cell) -> Self {
+        Kruskal {
+            start: Some(start),
+            walls: Vec::new(),
+            sets: Sets::new(),
+            cursor: None,
+        }
+    }
+}
+
+impl Generator for Kruskal {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            grid.visit(start);
+            self.walls = grid
+                .region(start)
+                .into_iter()
+                .flat_map(|c| grid.neighbors(c).into_iter().map(move |n| (c, n)))
+                .filter(|(c, n)| c < n)
+                .collect();
+            self.walls.shuffle(rng);
+        }
+
+        while let Some((a, b)) = self.walls.pop() {
+            if self.sets.union(a, b) {
+                grid.carve(a, b);
+                self.cursor = Some(b);
+                return Some(Change::Carve(a, b));
+            }
+        }
+        None
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        self.cursor
+    }
+}
@@ -0,0 +1,245 @@
+use std::path::{Path, PathBuf};
+
+use nannou::image::{self, imageops::FilterType};
+
+use super::grid::{Bits, Cell};
+
+/// The cells of a rectangle a maze may be carved through, so mazes can take any shape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    pub columns: usize,
+    pub rows: usize,
+    cells: Bits,
+    len: usize,
+}
+
+impl Mask {
+    /// The cells for which the function is true, from (0, 0) at the bottom left
+    pub fn from_fn(columns: usize, rows: usize, f: impl Fn(Cell) -> bool) -> Self {
+        let mut cells = Bits::new(columns * rows);
+        let mut len = 0;
+        for i in 0..columns * rows {
+            if f(((i % columns) as i32, (i / columns) as i32)) {
+                cells.set(i);
+                len += 1;
+            }
+        }
+        Mask {
+            columns,
+            rows,
+            cells,
+            len,
+        }
+    }
+
+    /// Every cell of the rectangle
+    pub fn rect(columns: usize, rows: usize) -> Self {
+        Mask::from_fn(columns, rows, |_| true)
+    }
+
+    /// The cells whose middles are inside a circle the diameter across
+    pub fn circle(diameter: usize) -> Self {
+        let radius = diameter as f32 / 2.0;
+        Mask::from_fn(diameter, diameter, |(x, y)| {
+            let (dx, dy) = (x as f32 + 0.5 - radius, y as f32 + 0.5 - radius);
+            dx * dx + dy * dy <= radius * radius
+        })
+    }
+
+    /// The text in a 5 by 7 block font, each pixel of a glyph `size` cells a side with a pixel
+    /// between glyphs. Letters are upper cased and characters without a glyph are left blank.
+    pub fn text(text: &str, size: usize) -> Self {
+        let glyphs: Vec<[u8; 7]> = text.chars().map(glyph).collect();
+        let size = size.max(1);
+        let columns = (glyphs.len() * 6).saturating_sub(1) * size;
+        let rows = 7 * size;
+        Mask::from_fn(columns, rows, |(x, y)| {
+            let (x, y) = (x as usize / size, y as usize / size);
+            // A glyph and the gap after it are six pixels wide, and glyph rows go top down
+            let (glyph, column) = (x / 6, x % 6);
+            column < 5 && glyphs[glyph][6 - y] & (0b10000 >> column) != 0
+        })
+    }
+
+    /// The dark pixels of an image, scaled to the number of columns and rows
+    pub fn image(path: impl AsRef<Path>, columns: usize, rows: usize) -> Result<Self, String> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .map_err(|e| format!("Couldn't open {}: {}", path.display(), e))?
+            .to_luma8();
+        let image =
+            image::imageops::resize(&image, columns as u32, rows as u32, FilterType::Triangle);
+        // Image rows go top down
+        Ok(Mask::from_fn(columns, rows, |(x, y)| {
+            image.get_pixel(x as u32, (rows - 1) as u32 - y as u32).0[0] < 128
+        }))
+    }
+
+    pub fn contains(&self, c: Cell) -> bool {
+        c.0 >= 0
+            && c.1 >= 0
+            && (c.0 as usize) < self.columns
+            && (c.1 as usize) < self.rows
+            && self.cells.get(c.1 as usize * self.columns + c.0 as usize)
+    }
+
+    /// Number of cells in the mask
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// How a mask is made, so a run can record it and make it again
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// Every cell of a rectangle, the columns and rows
+    Rect(usize, usize),
+    /// A circle the diameter across
+    Circle(usize),
+    /// The text with each pixel of a glyph the size a side
+    Text(String, usize),
+    /// The dark pixels of the image scaled to the columns and rows
+    Image(PathBuf, usize, usize),
+}
+
+impl Shape {
+    pub fn mask(&self) -> Result<Mask, String> {
+        match self {
+            Shape::Rect(columns, rows) => Ok(Mask::rect(*columns, *rows)),
+            Shape::Circle(diameter) => Ok(Mask::circle(*diameter)),
+            Shape::Text(text, size) => Ok(Mask::text(text, *size)),
+            Shape::Image(path, columns, rows) => Mask::image(path, *columns, *rows),
+        }
+    }
+}
+
+/// Rows of a character from the top, the high bit of the five on the left
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        'A' => [
+            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'B' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+        ],
+        'C' => [
+            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+        'D' => [
+            0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110,
+        ],
+        'E' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+        ],
+        'F' => [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'G' => [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+        'H' => [
+            0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+        'I' => [
+            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        'J' => [
+            0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+        'K' => [
+            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+        ],
+        'L' => [
+            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+        ],
+        'M' => [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+        'N' => [
+            0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+        ],
+        'O' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'P' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+        'Q' => [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+        ],
+        'R' => [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+        ],
+        'S' => [
+            0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+        ],
+        'T' => [
+            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+        'U' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+        'V' => [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+        'W' => [
+            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+        ],
+        'X' => [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+        ],
+        'Y' => [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+        'Z' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+        ],
+        '0' => [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+        '1' => [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+        '2' => [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+        '3' => [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+        '4' => [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+        '5' => [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+        '6' => [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+        '7' => [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+        '8' => [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+        '9' => [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+        '!' => [
+            0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100,
+        ],
+        '?' => [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+        ],
+        '.' => [
+            0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+        ],
+        '-' => [
+            0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+        ],
+        _ => [0; 7],
+    }
+}
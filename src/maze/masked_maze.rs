@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use nannou::{prelude::*, rand::random};
+
+use super::{mask::Shape, plan::Plan, Maze};
+use crate::{
+    capture::Session,
+    encode::{Encoding, Format},
+    palette::{named_color, Palette},
+    run::{Params, Run},
+    style::Style,
+};
+
+pub const SCALE: f32 = 16.0;
+
+pub fn model(_app: &App) -> Maze {
+    text(random(), "MAZE").unwrap()
+}
+
+/// A depth first maze through the cells of the shape, outlined along its edge. The choices are
+/// fully determined by the seed.
+pub fn masked(seed: u64, shape: Shape, scale: f32) -> Result<Maze, String> {
+    let plan = Plan::new(seed, "depth_first", "square", shape, scale);
+    Ok(plan
+        .maze()?
+        .styles(
+            Style::new(Palette::Solid(named_color(SLATEBLUE, 1.0)), 2.0),
+            Style::new(Palette::Solid(named_color(PALEGOLDENROD, 1.0)), scale / 2.0),
+        )
+        .background(None)
+        .outline()
+        .capture(
+            Session::new("masked_maze")
+                .run(Run::new("masked_maze", Params::Maze(plan)))
+                .encode(Format::Mp4, Encoding::default()),
+        ))
+}
+
+pub fn circle(seed: u64) -> Result<Maze, String> {
+    masked(seed, Shape::Circle(41), SCALE)
+}
+
+/// The text in block letters, each letter carved separately
+pub fn text(seed: u64, text: &str) -> Result<Maze, String> {
+    masked(seed, Shape::Text(String::from(text), 2), SCALE)
+}
+
+/// The dark parts of a black and white image, 48 cells across and high
+pub fn image(seed: u64, path: impl AsRef<Path>) -> Result<Maze, String> {
+    let path = path.as_ref().to_path_buf();
+    masked(seed, Shape::Image(path, 48, 48), SCALE)
+}
@@ -1,21 +1,359 @@
-use nannou::Draw;
+use nannou::{
+    color::Rgba,
+    prelude::*,
+    rand::{rngs::StdRng, SeedableRng},
+};
 
+use crate::{
+    capture::Session,
+    palette::{named_color, Palette},
+    segment::Segment,
+    style::Style,
+};
+
+pub mod aldous_broder;
+pub mod bench;
+pub mod binary_tree;
 pub mod breadth_first_maze;
 pub mod breadth_first_maze_growing;
+pub mod classic_maze;
 pub mod depth_first_maze;
+pub mod eller;
+pub mod grid;
+pub mod hunt_and_kill;
+pub mod kruskal;
+pub mod mask;
+pub mod masked_maze;
+pub mod plan;
+pub mod prim;
+pub mod recursive_division;
+pub mod sets;
+pub mod sidewinder;
+pub mod stats;
+pub mod tiled_maze;
+pub mod topology;
+pub mod wilson;
+
+use breadth_first_maze::BreadthFirst;
+use depth_first_maze::DepthFirst;
+use grid::{Cell, Grid};
+
+/// What a step of a generator did to the maze
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A passage was opened from the first cell to the second
+    Carve(Cell, Cell),
+    /// The generator is done with the passage and won't come back to it
+    Settle(Cell, Cell),
+}
+
+/// A maze algorithm, carving through a grid one step at a time
+pub trait Generator {
+    /// Make the next change to the grid. Returns None once the maze is complete.
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change>;
+
+    /// Carry on from an unvisited cell, for parts of a mask cut off from the cells carved so far
+    fn start(&mut self, cell: Cell);
+
+    /// The cell being worked from, if there is one
+    fn cursor(&self) -> Option<Cell> {
+        None
+    }
+}
+
+impl<G: Generator + ?Sized> Generator for Box<G> {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        (**self).step(grid, rng)
+    }
+
+    fn start(&mut self, cell: Cell) {
+        (**self).start(cell)
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        (**self).cursor()
+    }
+}
+
+/// Every generator by name, starting from the cell
+pub fn generators(start: Cell) -> Vec<(&'static str, Box<dyn Generator>)> {
+    let generators: [(&str, Box<dyn Generator>); 11] = [
+        ("depth_first", Box::new(DepthFirst::new(start))),
+        ("breadth_first", Box::new(BreadthFirst::new(start))),
+        ("kruskal", Box::new(kruskal::Kruskal::new(start))),
+        ("prim", Box::new(prim::Prim::new(start))),
+        ("wilson", Box::new(wilson::Wilson::new(start))),
+        (
+            "aldous_broder",
+            Box::new(aldous_broder::AldousBroder::new(start)),
+        ),
+        ("eller", Box::new(eller::Eller::new(start))),
+        (
+            "recursive_division",
+            Box::new(recursive_division::RecursiveDivision::new(start)),
+        ),
+        ("sidewinder", Box::new(sidewinder::Sidewinder::new(start))),
+        ("binary_tree", Box::new(binary_tree::BinaryTree::new(start))),
+        (
+            "hunt_and_kill",
+            Box::new(hunt_and_kill::HuntAndKill::new(start)),
+        ),
+    ];
+    generators.into_iter().collect()
+}
+
+/// The generator with the name, starting from the cell
+pub fn generator(name: &str, start: Cell) -> Option<Box<dyn Generator>> {
+    generators(start)
+        .into_iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, generator)| generator)
+}
+
+/// A maze being generated, with how to draw it and record it
+pub struct Maze {
+    pub grid: Grid,
+    generator: Box<dyn Generator>,
+    rng: StdRng,
+    pub scale: f32,
+    /// Passages carved but not settled
+    segments: Vec<Segment>,
+    /// Passages settled, in the order they were settled
+    finished_segments: Vec<Segment>,
+    /// Style of the carved and of the settled passages
+    pub styles: (Style, Style),
+    /// Cleared to each frame, None to draw over the frames before
+    pub background: Option<Rgba>,
+    /// Walls around the edge of the mask, if drawn
+    outline: Option<Vec<Segment>>,
+    pub outline_style: Style,
+    pub capture: Session,
+}
+
+impl Maze {
+    /// A maze whose choices are fully determined by the seed
+    pub fn new(grid: Grid, generator: impl Generator + 'static, seed: u64, scale: f32) -> Self {
+        let style = Style::new(Palette::Solid(named_color(PALEGOLDENROD, 1.0)), 10.0);
+        Maze {
+            grid,
+            generator: Box::new(generator),
+            rng: StdRng::seed_from_u64(seed),
+            scale,
+            segments: Vec::new(),
+            finished_segments: Vec::new(),
+            styles: (style.clone(), style),
+            background: Some(named_color(BLACK, 1.0)),
+            outline: None,
+            outline_style: Style::new(Palette::Solid(named_color(PALEGOLDENROD, 1.0)), 3.0),
+            capture: Session::new("maze"),
+        }
+    }
+
+    pub fn styles(mut self, carved: Style, settled: Style) -> Self {
+        self.styles = (carved, settled);
+        self
+    }
+
+    pub fn background(mut self, background: Option<Rgba>) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn outline(mut self) -> Self {
+        self.outline = Some(self.grid.outline(self.scale));
+        self
+    }
+
+    pub fn outline_style(mut self, style: Style) -> Self {
+        self.outline_style = style;
+        self
+    }
+
+    pub fn capture(mut self, session: Session) -> Self {
+        self.capture = session;
+        self
+    }
+
+    /// Passages carved and not yet settled
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn finished_segments(&self) -> &[Segment] {
+        &self.finished_segments
+    }
+
+    pub fn cursor(&self) -> Option<Cell> {
+        self.generator.cursor()
+    }
+
+    /// The area the finished maze fills, including its outline
+    pub fn area(&self) -> Rect {
+        self.grid.area(self.scale)
+    }
+
+    /// Middle of the cell in the drawing
+    pub fn point(&self, c: Cell) -> Vec2 {
+        self.grid.center(c) * self.scale
+    }
+
+    /// Advance the generator once. Returns false when there is nothing left to do.
+    pub fn step(&mut self) -> bool {
+        let change = loop {
+            match self.generator.step(&mut self.grid, &mut self.rng) {
+                Some(change) => break change,
+                // Cells left over are cut off from the rest by the mask, so start again there
+                None => match self.grid.next_unvisited() {
+                    Some(cell) => self.generator.start(cell),
+                    None => return false,
+                },
+            }
+        };
+        match change {
+            Change::Carve(a, b) => self
+                .segments
+                .push(Segment::from((self.point(a), self.point(b)))),
+            Change::Settle(a, b) => {
+                let (a, b) = (self.point(a), self.point(b));
+                // Usually the latest passage, so search from the end
+                let found = self.segments.iter().rposition(|s| {
+                    (s.start() == a && s.end() == b) || (s.start() == b && s.end() == a)
+                });
+                if let Some(i) = found {
+                    let segment = self.segments.remove(i);
+                    self.finished_segments.push(segment);
+                }
+            }
+        }
+        true
+    }
+}
+
+pub fn update(app: &App, model: &mut Maze, _update: Update) {
+    if !model.step() {
+        model.capture.stop(app);
+        app.quit();
+    }
+}
+
+pub fn view(app: &App, model: &Maze, frame: Frame) {
+    let draw = app.draw();
+    if let Some(background) = model.background {
+        draw.background().color(background);
+    }
+
+    model.styles.0.draw(&draw, &model.segments);
+    model.styles.1.draw(&draw, &model.finished_segments);
+    if let Some(walls) = &model.outline {
+        model.outline_style.draw(&draw, walls);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+
+    // Press R to start and stop recording
+    model.capture.capture(app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Number of groups of cells joined through neighbours
+    fn parts(grid: &Grid) -> usize {
+        let mut seen = std::collections::HashSet::new();
+        let mut parts = 0;
+        for cell in grid.cells() {
+            if seen.insert(cell) {
+                parts += 1;
+                let mut stack = vec![cell];
+                while let Some(c) = stack.pop() {
+                    stack.extend(grid.neighbors(c).into_iter().filter(|n| seen.insert(*n)));
+                }
+            }
+        }
+        parts
+    }
+
+    /// Run the maze to the end and check it joins every cell it can reach without loops, naming
+    /// the maze in any failure
+    fn assert_spanning_tree(mut maze: Maze, name: &str) {
+        while maze.step() {}
+        let grid = &maze.grid;
+        assert_eq!(grid.remaining(), 0, "{}", name);
+        assert_eq!(grid.passages().len(), grid.len() - parts(grid), "{}", name);
+        for (a, b) in grid.passages() {
+            assert!(
+                grid.neighbors(*a).contains(b),
+                "{} {:?} to {:?}",
+                name,
+                a,
+                b
+            );
+            assert!(
+                grid.is_open(*a, *b) && grid.is_open(*b, *a),
+                "{} {:?} to {:?}",
+                name,
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn generators_make_spanning_trees() {
+        assert_spanning_tree(depth_first_maze::seeded(3), "depth_first_maze");
+        assert_spanning_tree(breadth_first_maze::seeded(3), "breadth_first_maze");
+    }
+
+    #[test]
+    fn every_generator_makes_spanning_trees() {
+        let shapes = [
+            mask::Mask::rect(9, 6),
+            mask::Mask::circle(12),
+            mask::Mask::text("OX 4", 1),
+        ];
+        for mask in shapes {
+            let grid = Grid::masked(mask);
+            let start = grid.middle().unwrap();
+            for (name, generator) in generators(start) {
+                assert_spanning_tree(Maze::new(grid.clone(), generator, 5, 1.0), name);
+            }
+        }
+    }
+
+    #[test]
+    fn generators_work_on_every_topology() {
+        let grids = [
+            Grid::hex(7, 6),
+            Grid::triangle(9, 5),
+            Grid::octagon(5, 4),
+            Grid::polar(4),
+            Grid::tiled(topology::Topology::Hex, mask::Mask::circle(9)),
+        ];
+        for grid in grids {
+            let start = grid.middle().unwrap();
+            for (name, generator) in generators(start) {
+                let name = format!("{:?} {}", grid.topology(), name);
+                let maze = Maze::new(grid.clone(), generator, 5, 1.0).outline();
+                assert!(!maze.outline.as_ref().unwrap().is_empty(), "{}", name);
+                assert_spanning_tree(maze, &name);
+            }
+        }
+    }
+
+    #[test]
+    fn masks_shape_the_maze() {
+        let rect = Maze::new(Grid::new(7, 3), DepthFirst::new((0, 0)), 3, 1.0);
+        assert_spanning_tree(rect, "rect");
+
+        // Both cut off parts of the mask and cells joined only at corners get carved
+        let maze = masked_maze::text(3, "MAZE 42").unwrap();
+        assert!(parts(&maze.grid) > 1);
+        assert!(!maze.grid.contains((6, 0)));
+        assert_spanning_tree(maze, "text");
 
-pub trait SquareMaze {
-    fn cursor(&self) -> (i32, i32);
-    fn scale(&self) -> f32;
-    fn width(&self) -> usize;
-    fn cells(&self) -> &mut Vec<(i32, i32)>;
-    fn neighbors(&self, p: (i32, i32)) -> Vec<(i32, i32)>;
-
-    fn outline<'a>(
-        &'a self,
-        draw: &'a Draw,
-    ) -> nannou::draw::Drawing<nannou::draw::primitive::Rect> {
-        let r_size = (self.width() + 1) as f32 * self.scale() * 2.0;
-        draw.rect().w_h(r_size, r_size)
+        let circle = masked_maze::circle(3).unwrap();
+        assert!(circle.grid.contains((20, 20)) && !circle.grid.contains((0, 0)));
+        assert_spanning_tree(circle, "circle");
     }
 }
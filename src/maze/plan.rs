@@ -0,0 +1,48 @@
+use super::{generator, grid::Grid, mask::Shape, topology::Topology, Maze};
+
+/// Everything that decides the passages of a maze, so a run can be described and repeated
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub seed: u64,
+    /// Name of the generator, as `generators` knows it
+    pub algorithm: String,
+    /// Name of the topology. Polar grids have a ring for each row of the mask.
+    pub topology: String,
+    pub shape: Shape,
+    pub scale: f32,
+}
+
+impl Plan {
+    pub fn new(seed: u64, algorithm: &str, topology: &str, shape: Shape, scale: f32) -> Self {
+        Plan {
+            seed,
+            algorithm: String::from(algorithm),
+            topology: String::from(topology),
+            shape,
+            scale,
+        }
+    }
+
+    /// A square of 2 * width + 1 cells a side, like `Grid::square`
+    pub fn square(seed: u64, algorithm: &str, width: i32, scale: f32) -> Self {
+        let side = (width.max(0) * 2 + 1) as usize;
+        Plan::new(seed, algorithm, "square", Shape::Rect(side, side), scale)
+    }
+
+    /// The cells of the shape laid out by the topology
+    pub fn grid(&self) -> Result<Grid, String> {
+        let mask = self.shape.mask()?;
+        let topology = Topology::named(&self.topology, mask.rows)
+            .ok_or_else(|| format!("no maze topology called {}", self.topology))?;
+        Ok(Grid::tiled(topology, mask))
+    }
+
+    /// The maze carved from the middle of the grid, with the default styles
+    pub fn maze(&self) -> Result<Maze, String> {
+        let grid = self.grid()?;
+        let start = grid.middle().unwrap_or((0, 0));
+        let generator = generator(&self.algorithm, start)
+            .ok_or_else(|| format!("no maze algorithm called {}", self.algorithm))?;
+        Ok(Maze::new(grid, generator, self.seed, self.scale))
+    }
+}
@@ -0,0 +1,64 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use nannou::rand::{rngs::StdRng, Rng};
+
+use super::{
+    grid::{Cell, Grid},
+    Change, Generator,
+};
+
+/// Prim's algorithm on random weights. Each wall out of the maze gets a random weight when it is
+/// first reached, and the lightest is carved through each step, growing a minimum spanning tree
+/// outwards from the start.
+pub struct Prim {
+    start: Option<Cell>,
+    /// Walls from a cell in the maze to a neighbour, lightest first
+    frontier: BinaryHeap<Reverse<(u32, Cell, Cell)>>,
+    cursor: Option<Cell>,
+}
+
+impl Prim {
+    pub fn new(start: Cell) -> Self {
+        Prim {
+            start: Some(start),
+            frontier: BinaryHeap::new(),
+            cursor: None,
+        }
+    }
+
+    fn reach(&mut self, grid: &Grid, c: Cell, rng: &mut StdRng) {
+        for n in grid.unvisited_neighbors(c) {
+            self.frontier.push(Reverse((rng.gen(), c, n)));
+        }
+    }
+}
+
+impl Generator for Prim {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            grid.visit(start);
+            self.frontier.clear();
+            self.reach(grid, start, rng);
+            self.cursor = Some(start);
+        }
+
+        // Walls into cells reached another way since they were weighed are left standing
+        while let Some(Reverse((_, a, b))) = self.frontier.pop() {
+            if !grid.is_visited(b) {
+                grid.carve(a, b);
+                self.reach(grid, b, rng);
+                self.cursor = Some(b);
+                return Some(Change::Carve(a, b));
+            }
+        }
+        None
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        self.cursor
+    }
+}
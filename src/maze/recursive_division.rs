@@ -0,0 +1,131 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use nannou::rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use super::{
+    grid::{Cell, Grid},
+    sets::Sets,
+    Change, Generator,
+};
+
+/// Recursive division. Splits the maze in two with a straight wall across its longer side, leaves
+/// a single gap in the wall and divides each side the same way until the pieces are single
+/// cells. It builds walls rather than carving passages, so it's drawn as the gaps it leaves, each
+/// wall's before those of the walls dividing the pieces either side.
+pub struct RecursiveDivision {
+    start: Option<Cell>,
+    /// Pieces of joined cells still to divide
+    pieces: Vec<Vec<Cell>>,
+    /// Gaps left in the latest wall, to carve
+    gaps: Vec<(Cell, Cell)>,
+    cursor: Option<Cell>,
+}
+
+impl RecursiveDivision {
+    pub fn new(start: Cell) -> Self {
+        RecursiveDivision {
+            start: Some(start),
+            pieces: Vec::new(),
+            gaps: Vec::new(),
+            cursor: None,
+        }
+    }
+
+    /// Split the piece along a line of cells, leaving a gap between each pair of the pieces it
+    /// falls into that need one to stay joined
+    fn divide(&mut self, grid: &Grid, piece: Vec<Cell>, rng: &mut StdRng) {
+        let bounds = |axis: fn(&Cell) -> i32| {
+            let values = piece.iter().map(axis);
+            (values.clone().min().unwrap_or(0), values.max().unwrap_or(0))
+        };
+        let (x, y) = (bounds(|c| c.0), bounds(|c| c.1));
+        let across = match (x.1 - x.0).cmp(&(y.1 - y.0)) {
+            Ordering::Greater => true,
+            Ordering::Less => false,
+            Ordering::Equal => rng.gen(),
+        };
+        let axis = |c: &Cell| if across { c.0 } else { c.1 };
+        let (low, high) = if across { x } else { y };
+        let wall = rng.gen_range(low + 1..=high);
+        let (before, after): (Vec<Cell>, Vec<Cell>) =
+            piece.iter().copied().partition(|c| axis(c) < wall);
+
+        // In shapes other than rectangles either side may fall apart into more than one piece
+        let mut pieces = split(grid, before);
+        pieces.extend(split(grid, after));
+        let label: HashMap<Cell, usize> = pieces
+            .iter()
+            .enumerate()
+            .flat_map(|(i, p)| p.iter().map(move |c| (*c, i)))
+            .collect();
+        let mut walls: Vec<(Cell, Cell)> = piece
+            .iter()
+            .flat_map(|c| grid.neighbors(*c).into_iter().map(move |n| (*c, n)))
+            .filter(|(c, n)| c < n && label.get(n).is_some_and(|l| *l != label[c]))
+            .collect();
+        walls.shuffle(rng);
+        let mut sets = Sets::new();
+        for (a, b) in walls {
+            if sets.union(pieces[label[&a]][0], pieces[label[&b]][0]) {
+                self.gaps.push((a, b));
+            }
+        }
+        self.pieces.extend(pieces);
+    }
+}
+
+/// The cells in groups joined through neighbours among them
+fn split(grid: &Grid, cells: Vec<Cell>) -> Vec<Vec<Cell>> {
+    let mut unassigned: HashSet<Cell> = cells.iter().copied().collect();
+    let mut pieces = Vec::new();
+    for c in cells {
+        if !unassigned.remove(&c) {
+            continue;
+        }
+        let mut piece = vec![c];
+        let mut i = 0;
+        while i < piece.len() {
+            for n in grid.neighbors(piece[i]) {
+                if unassigned.remove(&n) {
+                    piece.push(n);
+                }
+            }
+            i += 1;
+        }
+        pieces.push(piece);
+    }
+    pieces
+}
+
+impl Generator for RecursiveDivision {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            grid.visit(start);
+            self.pieces = vec![grid.region(start)];
+            self.gaps.clear();
+        }
+
+        loop {
+            if let Some((a, b)) = self.gaps.pop() {
+                grid.carve(a, b);
+                self.cursor = Some(b);
+                return Some(Change::Carve(a, b));
+            }
+            let piece = self.pieces.pop()?;
+            if piece.len() > 1 {
+                self.divide(grid, piece, rng);
+            }
+        }
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        self.cursor
+    }
+}
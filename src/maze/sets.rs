@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use nannou::rand::{rngs::StdRng, seq::SliceRandom};
+
+use super::{
+    grid::{Cell, Grid},
+    Change,
+};
+
+/// Groups of cells joined by passages, to tell whether a passage between two cells would make a
+/// loop
+#[derive(Debug, Clone, Default)]
+pub struct Sets {
+    /// A cell in the same group as each cell, leading to the one standing for the group. Cells
+    /// not yet in a group are alone in their own.
+    parent: HashMap<Cell, Cell>,
+}
+
+impl Sets {
+    pub fn new() -> Self {
+        Sets::default()
+    }
+
+    /// The cell standing for the group the cell is in
+    pub fn find(&mut self, c: Cell) -> Cell {
+        let mut c = c;
+        while let Some(&parent) = self.parent.get(&c) {
+            if parent == c {
+                break;
+            }
+            // Skip every other cell on the way up so the next search is shorter
+            let grandparent = self.parent.get(&parent).copied().unwrap_or(parent);
+            self.parent.insert(c, grandparent);
+            c = grandparent;
+        }
+        c
+    }
+
+    pub fn same(&mut self, a: Cell, b: Cell) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Put the cells' groups together. Returns false if they were already in the same group.
+    pub fn union(&mut self, a: Cell, b: Cell) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        self.parent.insert(a, b);
+        true
+    }
+
+    /// Carve from the first of the cells with a neighbour in another group to that neighbour,
+    /// skipping past cells with none. For the pieces a generator working through rows leaves
+    /// apart in shapes other than rectangles.
+    pub fn join(
+        &mut self,
+        grid: &mut Grid,
+        cells: &[Cell],
+        next: &mut usize,
+        rng: &mut StdRng,
+    ) -> Option<Change> {
+        while let Some(&c) = cells.get(*next) {
+            let mut neighbors = grid.neighbors(c);
+            neighbors.shuffle(rng);
+            for n in neighbors {
+                if self.union(c, n) {
+                    grid.carve(c, n);
+                    return Some(Change::Carve(c, n));
+                }
+            }
+            *next += 1;
+        }
+        None
+    }
+}
@@ -0,0 +1,94 @@
+use nannou::rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use super::{
+    grid::{Cell, Grid},
+    sets::Sets,
+    Change, Generator,
+};
+
+/// The sidewinder algorithm. Goes through the cells row by row, carving runs of passages to the
+/// right and ending each run at random by carving up from one of its cells. The top row has
+/// nowhere to go up so it's a single corridor.
+pub struct Sidewinder {
+    start: Option<Cell>,
+    /// Cells joined to the start, row by row from the bottom left
+    cells: Vec<Cell>,
+    next: usize,
+    /// Cells of the row joined by passages since the last passage up
+    run: Vec<Cell>,
+    sets: Sets,
+    /// Index of the cell being joined to its neighbours once every row is done
+    joined: usize,
+}
+
+impl Sidewinder {
+    pub fn new(start: Cell) -> Self {
+        Sidewinder {
+            start: Some(start),
+            cells: Vec::new(),
+            next: 0,
+            run: Vec::new(),
+            sets: Sets::new(),
+            joined: 0,
+        }
+    }
+}
+
+impl Generator for Sidewinder {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            self.cells = grid.region(start);
+            self.cells.sort_by_key(|c| (c.1, c.0));
+            self.next = 0;
+            self.run.clear();
+            self.sets = Sets::new();
+            self.joined = 0;
+        }
+
+        while let Some(&c) = self.cells.get(self.next) {
+            self.next += 1;
+            grid.visit(c);
+            self.run.push(c);
+            let right = grid
+                .neighbors(c)
+                .into_iter()
+                .find(|n| n.1 == c.1 && n.0 > c.0);
+
+            // End the run at the end of the row or at random
+            let mut passage = right.map(|right| (c, right));
+            if right.is_none() || rng.gen() {
+                let up: Vec<(Cell, Cell)> = self
+                    .run
+                    .iter()
+                    .flat_map(|a| grid.neighbors(*a).into_iter().map(move |b| (*a, b)))
+                    .filter(|(a, b)| b.1 > a.1)
+                    .collect();
+                // Unless it's the top row and there's nowhere to go but right
+                if let Some(&up) = up.choose(rng) {
+                    passage = Some(up);
+                    self.run.clear();
+                } else if right.is_none() {
+                    self.run.clear();
+                }
+            }
+
+            if let Some((a, b)) = passage {
+                if self.sets.union(a, b) {
+                    grid.carve(a, b);
+                    return Some(Change::Carve(a, b));
+                }
+            }
+        }
+
+        // A rectangle is joined up by now, other shapes may be left in pieces
+        self.sets.join(grid, &self.cells, &mut self.joined, rng)
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        self.cells.get(self.next).copied()
+    }
+}
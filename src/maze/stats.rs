@@ -0,0 +1,118 @@
+use std::collections::{HashMap, HashSet};
+
+use super::grid::{Cell, Grid};
+
+/// Measures of the texture of a finished maze, to compare what generators make
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// Share of the cells with a single passage
+    pub dead_ends: f32,
+    /// Share of the cells with three or more passages
+    pub junctions: f32,
+    /// Share of the cells with two passages that go straight through them
+    pub straight: f32,
+    /// Cells along the longest path through the maze as a share of all of them
+    pub longest: f32,
+}
+
+impl Stats {
+    pub fn of(grid: &Grid) -> Self {
+        let mut passages: HashMap<Cell, Vec<Cell>> = HashMap::new();
+        for (a, b) in grid.passages() {
+            passages.entry(*a).or_default().push(*b);
+            passages.entry(*b).or_default().push(*a);
+        }
+
+        let (mut dead_ends, mut junctions, mut corridors, mut straight) = (0, 0, 0, 0);
+        for c in grid.cells() {
+            match passages.get(&c).map_or(&[][..], |p| p.as_slice()) {
+                [_] => dead_ends += 1,
+                [a, b] => {
+                    corridors += 1;
+                    let center = grid.center(c);
+                    let ways = (grid.center(*a) - center, grid.center(*b) - center);
+                    if (ways.0.normalize() + ways.1.normalize()).length() < 1e-3 {
+                        straight += 1;
+                    }
+                }
+                [] => {}
+                _ => junctions += 1,
+            }
+        }
+
+        // The farthest cell from any cell is an end of the longest path through its piece
+        let mut seen = HashSet::new();
+        let mut longest = 0;
+        for c in grid.cells() {
+            if seen.insert(c) {
+                let (end, _) = farthest(&passages, c, &mut seen);
+                let (_, length) = farthest(&passages, end, &mut HashSet::new());
+                longest = longest.max(length + 1);
+            }
+        }
+
+        let share = |n: usize, of: usize| n as f32 / of.max(1) as f32;
+        Stats {
+            dead_ends: share(dead_ends, grid.len()),
+            junctions: share(junctions, grid.len()),
+            straight: share(straight, corridors),
+            longest: share(longest, grid.len()),
+        }
+    }
+
+    /// The mean of each measure
+    pub fn mean(stats: &[Stats]) -> Self {
+        let n = stats.len().max(1) as f32;
+        let sum = |f: fn(&Stats) -> f32| stats.iter().map(f).sum::<f32>() / n;
+        Stats {
+            dead_ends: sum(|s| s.dead_ends),
+            junctions: sum(|s| s.junctions),
+            straight: sum(|s| s.straight),
+            longest: sum(|s| s.longest),
+        }
+    }
+}
+
+/// The cell farthest from the start through the passages and the number of passages to it,
+/// marking every cell reached as seen
+fn farthest(
+    passages: &HashMap<Cell, Vec<Cell>>,
+    start: Cell,
+    seen: &mut HashSet<Cell>,
+) -> (Cell, usize) {
+    seen.insert(start);
+    let mut layer = vec![start];
+    let mut distance = 0;
+    loop {
+        let next: Vec<Cell> = layer
+            .iter()
+            .flat_map(|c| passages.get(c).into_iter().flatten())
+            .copied()
+            .filter(|n| seen.insert(*n))
+            .collect();
+        if next.is_empty() {
+            return (layer[0], distance);
+        }
+        layer = next;
+        distance += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corridor_and_corner() {
+        // A corridor along the bottom of a 3 by 2 grid, turning up at the end
+        let mut grid = Grid::new(3, 2);
+        grid.carve((0, 0), (1, 0));
+        grid.carve((1, 0), (2, 0));
+        grid.carve((2, 0), (2, 1));
+        let stats = Stats::of(&grid);
+        assert_eq!(stats.dead_ends, 2.0 / 6.0);
+        assert_eq!(stats.junctions, 0.0);
+        assert_eq!(stats.straight, 0.5);
+        assert_eq!(stats.longest, 4.0 / 6.0);
+    }
+}
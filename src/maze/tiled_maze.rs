@@ -0,0 +1,65 @@
+use nannou::{
+    prelude::*,
+    rand::{random, seq::SliceRandom, thread_rng},
+};
+
+use super::{grid::Grid, mask::Shape, plan::Plan, Maze};
+use crate::{
+    capture::Session,
+    encode::{Encoding, Format},
+    palette::{named_color, Palette},
+    run::{Params, Run},
+    style::Style,
+};
+
+pub const SCALE: f32 = 20.0;
+
+/// The grids, by name
+pub const GRIDS: [&str; 5] = ["square", "hex", "triangle", "octagon", "polar"];
+
+/// A grid of each topology about 31 units across
+pub fn grid(name: &str) -> Option<Grid> {
+    match name {
+        "square" => Some(Grid::new(31, 31)),
+        "hex" => Some(Grid::hex(30, 35)),
+        "triangle" => Some(Grid::triangle(61, 35)),
+        "octagon" => Some(Grid::octagon(31, 31)),
+        "polar" => Some(Grid::polar(15)),
+        _ => None,
+    }
+}
+
+/// The grid named by the MAZE_GRID environment variable and the algorithm named by MAZE, or
+/// either at random
+pub fn model(_app: &App) -> Maze {
+    let pick = |variable: &str, names: &[&str]| {
+        std::env::var(variable)
+            .unwrap_or_else(|_| names.choose(&mut thread_rng()).unwrap().to_string())
+    };
+    let names: Vec<&str> = super::generators((0, 0))
+        .into_iter()
+        .map(|(n, _)| n)
+        .collect();
+    seeded(&pick("MAZE_GRID", &GRIDS), &pick("MAZE", &names), random()).unwrap()
+}
+
+/// A maze through the named grid by the named algorithm, starting in the middle and outlined
+/// along the edge. The choices are fully determined by the seed.
+pub fn seeded(grid_name: &str, algorithm: &str, seed: u64) -> Result<Maze, String> {
+    let grid = grid(grid_name).ok_or_else(|| format!("no maze grid called {}", grid_name))?;
+    let shape = Shape::Rect(grid.columns, grid.rows);
+    let plan = Plan::new(seed, algorithm, grid.topology().name(), shape, SCALE);
+    // Narrow enough for the passages between triangles, the closest cells of any grid
+    let style = Style::new(Palette::Solid(named_color(PALEGOLDENROD, 1.0)), SCALE * 0.3);
+    Ok(plan
+        .maze()?
+        .styles(style.clone(), style)
+        .background(Some(named_color(LIGHTSLATEGRAY, 1.0)))
+        .outline()
+        .outline_style(Style::new(Palette::Solid(named_color(BLACK, 1.0)), 3.0))
+        .capture(
+            Session::new(format!("tiled_maze/{}_{}", grid_name, algorithm))
+                .run(Run::new("tiled_maze", Params::Maze(plan)))
+                .encode(Format::Mp4, Encoding::default()),
+        ))
+}
@@ -0,0 +1,268 @@
+use std::f32::consts::{PI, TAU};
+
+use nannou::prelude::Vec2;
+
+use super::grid::Cell;
+
+/// Height of a row of hexagons or triangles one unit across
+const ROW: f32 = 0.866_025_4;
+
+/// How cells tile the plane, which cells are next to each other and the shape of each
+#[derive(Debug, Clone, PartialEq)]
+pub enum Topology {
+    /// Squares with four neighbours each
+    Square,
+    /// Hexagons pointing up with six neighbours each, odd rows shifted half a cell right
+    Hex,
+    /// Triangles with three neighbours each, pointing up where the coordinates add up to an
+    /// even number and down where they're odd
+    Triangle,
+    /// Octagons with eight neighbours each and squares with four in the gaps between them, on a
+    /// grid of half steps. Octagons are where both coordinates are even and squares where both
+    /// are odd.
+    Octagon,
+    /// Rings of cells around a single cell in the middle, with cells numbered around each ring
+    /// and the rings numbered outwards. A ring splits each cell of the ring inside it when they'd
+    /// get too wide, so the cells stay about square.
+    Polar {
+        rings: usize,
+        /// Cells in each ring, with one more ring than there are to shape the outside edge
+        counts: Vec<usize>,
+    },
+}
+
+impl Topology {
+    /// The rings of a polar grid, each a unit wide
+    pub fn polar(rings: usize) -> Self {
+        let mut counts = vec![1];
+        for ring in 1..=rings {
+            let inner = counts[ring - 1];
+            // How many times each cell inside can be split and leave cells about a unit wide
+            let split = (TAU * ring as f32 / inner as f32).round().max(1.0) as usize;
+            counts.push(inner * split);
+        }
+        Topology::Polar { rings, counts }
+    }
+
+    /// The topology with the name, for polar grids with the number of rings
+    pub fn named(name: &str, rings: usize) -> Option<Self> {
+        match name {
+            "square" => Some(Topology::Square),
+            "hex" => Some(Topology::Hex),
+            "triangle" => Some(Topology::Triangle),
+            "octagon" => Some(Topology::Octagon),
+            "polar" => Some(Topology::polar(rings)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topology::Square => "square",
+            Topology::Hex => "hex",
+            Topology::Triangle => "triangle",
+            Topology::Octagon => "octagon",
+            Topology::Polar { .. } => "polar",
+        }
+    }
+
+    /// False for positions of a rectangle of cells that aren't cells, such as the gaps between
+    /// octagons and squares or past the end of a ring
+    pub fn contains(&self, c: Cell) -> bool {
+        match self {
+            Topology::Octagon => c.0.rem_euclid(2) == c.1.rem_euclid(2),
+            Topology::Polar { rings, counts } => {
+                c.0 >= 0
+                    && c.1 >= 0
+                    && (c.1 as usize) < *rings
+                    && (c.0 as usize) < counts[c.1 as usize]
+            }
+            _ => true,
+        }
+    }
+
+    /// Cells across each side of the cell, which may be outside the grid. Sides stay in the same
+    /// order as `sides`.
+    pub fn neighbors(&self, c: Cell) -> Vec<Cell> {
+        let (x, y) = c;
+        match self {
+            Topology::Square => vec![(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)],
+            Topology::Hex => {
+                let shift = y.rem_euclid(2);
+                vec![
+                    (x - 1, y),
+                    (x + 1, y),
+                    (x + shift - 1, y - 1),
+                    (x + shift, y - 1),
+                    (x + shift - 1, y + 1),
+                    (x + shift, y + 1),
+                ]
+            }
+            Topology::Triangle => {
+                let across = if pointing_up(c) { y - 1 } else { y + 1 };
+                vec![(x - 1, y), (x + 1, y), (x, across)]
+            }
+            Topology::Octagon if x.rem_euclid(2) == 0 => vec![
+                (x - 2, y),
+                (x + 2, y),
+                (x, y - 2),
+                (x, y + 2),
+                (x - 1, y - 1),
+                (x + 1, y - 1),
+                (x - 1, y + 1),
+                (x + 1, y + 1),
+            ],
+            Topology::Octagon => vec![
+                (x - 1, y - 1),
+                (x + 1, y - 1),
+                (x - 1, y + 1),
+                (x + 1, y + 1),
+            ],
+            Topology::Polar { counts, .. } => {
+                let ring = y.max(0) as usize;
+                let count = counts[ring] as i32;
+                let mut neighbors = Vec::new();
+                if count > 1 {
+                    neighbors.push(((x - 1).rem_euclid(count), y));
+                    neighbors.push(((x + 1).rem_euclid(count), y));
+                }
+                if ring > 0 {
+                    neighbors.push((x / (count / counts[ring - 1] as i32), y - 1));
+                }
+                let split = (counts[ring + 1] / counts[ring]) as i32;
+                neighbors.extend((0..split).map(|k| (x * split + k, y + 1)));
+                neighbors
+            }
+        }
+    }
+
+    /// Middle of the cell, in units of about a cell across
+    pub fn center(&self, c: Cell) -> Vec2 {
+        let (x, y) = (c.0 as f32, c.1 as f32);
+        match self {
+            Topology::Square => Vec2::new(x, y),
+            Topology::Hex => Vec2::new(x + 0.5 * c.1.rem_euclid(2) as f32, y * ROW),
+            Topology::Triangle => {
+                let third = if pointing_up(c) { 1.0 } else { 2.0 };
+                Vec2::new(x * 0.5, (y + third / 3.0) * ROW)
+            }
+            Topology::Octagon => Vec2::new(x, y) * 0.5,
+            Topology::Polar { counts, .. } => {
+                if c.1 == 0 {
+                    return Vec2::ZERO;
+                }
+                let angle = (x + 0.5) / counts[c.1 as usize] as f32 * TAU;
+                Vec2::new(angle.cos(), angle.sin()) * (y + 0.5)
+            }
+        }
+    }
+
+    /// Each side of the cell as the cell across it and the corners at either end
+    pub fn sides(&self, c: Cell) -> Vec<(Cell, Vec2, Vec2)> {
+        let neighbors = self.neighbors(c);
+        let (corners, inradius, facing): (usize, f32, &[f32]) = match self {
+            Topology::Square => (4, 0.5, &[180.0, 0.0, 270.0, 90.0]),
+            Topology::Hex => (6, 0.5, &[180.0, 0.0, 240.0, 300.0, 120.0, 60.0]),
+            Topology::Triangle if pointing_up(c) => (3, ROW / 3.0, &[150.0, 30.0, 270.0]),
+            Topology::Triangle => (3, ROW / 3.0, &[210.0, 330.0, 90.0]),
+            Topology::Octagon if neighbors.len() == 8 => (
+                8,
+                0.5,
+                &[180.0, 0.0, 270.0, 90.0, 225.0, 315.0, 135.0, 45.0],
+            ),
+            // The squares between octagons are turned to face them
+            Topology::Octagon => (4, 0.5 * 2.0f32.sqrt() - 0.5, &[225.0, 315.0, 135.0, 45.0]),
+            Topology::Polar { counts, .. } => return polar_sides(counts, c, neighbors),
+        };
+
+        // A regular polygon around the middle of the cell, the sides facing the angles given
+        let center = self.center(c);
+        let half = PI / corners as f32;
+        let radius = inradius / half.cos();
+        let corner = |angle: f32| center + Vec2::new(angle.cos(), angle.sin()) * radius;
+        neighbors
+            .into_iter()
+            .zip(facing)
+            .map(|(n, facing)| {
+                let facing = facing.to_radians();
+                (n, corner(facing - half), corner(facing + half))
+            })
+            .collect()
+    }
+}
+
+/// Sides of a cell of a polar grid, straight along the edges between rings and across them, in
+/// the order of the neighbours
+fn polar_sides(counts: &[usize], c: Cell, neighbors: Vec<Cell>) -> Vec<(Cell, Vec2, Vec2)> {
+    let (x, y) = (c.0 as f32, c.1 as f32);
+    let count = counts[c.1 as usize] as f32;
+    let point = |turn: f32, radius: f32| {
+        let angle = turn * TAU;
+        Vec2::new(angle.cos(), angle.sin()) * radius
+    };
+    let (start, end) = (x / count, (x + 1.0) / count);
+    let outer = y + 1.0;
+    neighbors
+        .into_iter()
+        .map(|n| {
+            let (a, b) = if n.1 == c.1 && n.0 == (c.0 + 1) % count as i32 {
+                (point(end, y), point(end, outer))
+            } else if n.1 == c.1 {
+                (point(start, y), point(start, outer))
+            } else if n.1 < c.1 {
+                (point(start, y), point(end, y))
+            } else {
+                // Each cell outside covers an equal part of this one's outer edge
+                let outside = counts[n.1 as usize] as f32;
+                (
+                    point(n.0 as f32 / outside, outer),
+                    point((n.0 + 1) as f32 / outside, outer),
+                )
+            };
+            (n, a, b)
+        })
+        .collect()
+}
+
+fn pointing_up(c: Cell) -> bool {
+    (c.0 + c.1).rem_euclid(2) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every side shared with a neighbour should be a side of the neighbour too, in the same place
+    #[test]
+    fn neighbors_share_sides() {
+        let topologies = [
+            (Topology::Square, 5, 4),
+            (Topology::Hex, 5, 4),
+            (Topology::Triangle, 6, 4),
+            (Topology::Octagon, 7, 5),
+            (Topology::polar(5), 48, 5),
+        ];
+        for (topology, columns, rows) in topologies {
+            let contains = |c: Cell| {
+                topology.contains(c) && c.0 >= 0 && c.1 >= 0 && c.0 < columns && c.1 < rows
+            };
+            for y in 0..rows {
+                for x in (0..columns).filter(|x| contains((*x, y))) {
+                    for (n, a, b) in topology.sides((x, y)) {
+                        if !contains(n) {
+                            continue;
+                        }
+                        let shared = topology.sides(n).into_iter().any(|(m, c, d)| {
+                            m == (x, y)
+                                && ((a.distance(c) < 1e-4 && b.distance(d) < 1e-4)
+                                    || (a.distance(d) < 1e-4 && b.distance(c) < 1e-4))
+                        });
+                        assert!(shared, "{:?} {:?} to {:?}", topology, (x, y), n);
+                        let apart = topology.center((x, y)).distance(topology.center(n));
+                        assert!(apart > 0.3 && apart < 1.6, "{:?} {:?}", topology, (x, y));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use nannou::rand::{rngs::StdRng, seq::SliceRandom};
+
+use super::{
+    grid::{Cell, Grid},
+    Change, Generator,
+};
+
+/// Wilson's algorithm. Walks at random from a cell outside the maze until the walk reaches the
+/// maze, erasing any loops the walk makes, then carves along the walk a passage per step. Every
+/// maze is as likely as every other, like Aldous-Broder, but the walks get shorter as the maze
+/// grows instead of longer.
+pub struct Wilson {
+    start: Option<Cell>,
+    /// Cells joined to the start in a random order, to walk from
+    cells: Vec<Cell>,
+    next: usize,
+    /// The latest walk with its loops erased, from the maze outwards
+    walk: Vec<Cell>,
+    /// Number of passages along the walk carved so far
+    carved: usize,
+}
+
+impl Wilson {
+    pub fn new(start: Cell) -> Self {
+        Wilson {
+            start: Some(start),
+            cells: Vec::new(),
+            next: 0,
+            walk: Vec::new(),
+            carved: 0,
+        }
+    }
+
+    /// Walk from the cell until reaching the maze, keeping only the way back out of each loop
+    fn walk(&mut self, grid: &Grid, from: Cell, rng: &mut StdRng) {
+        self.walk = vec![from];
+        self.carved = 0;
+        let mut positions = HashMap::from([(from, 0)]);
+        loop {
+            let head = self.walk[self.walk.len() - 1];
+            let next = match grid.neighbors(head).choose(rng) {
+                Some(next) => *next,
+                None => return,
+            };
+            if grid.is_visited(next) {
+                self.walk.push(next);
+                break;
+            }
+            match positions.get(&next) {
+                Some(&i) => {
+                    for c in self.walk.drain(i + 1..) {
+                        positions.remove(&c);
+                    }
+                }
+                None => {
+                    positions.insert(next, self.walk.len());
+                    self.walk.push(next);
+                }
+            }
+        }
+        self.walk.reverse();
+    }
+}
+
+impl Generator for Wilson {
+    fn step(&mut self, grid: &mut Grid, rng: &mut StdRng) -> Option<Change> {
+        if let Some(start) = self.start.take() {
+            grid.visit(start);
+            self.cells = grid.region(start);
+            self.cells.shuffle(rng);
+            self.next = 0;
+            self.walk.clear();
+        }
+
+        if self.carved + 1 >= self.walk.len() {
+            while grid.is_visited(*self.cells.get(self.next)?) {
+                self.next += 1;
+            }
+            self.walk(grid, self.cells[self.next], rng);
+        }
+
+        let (a, b) = (self.walk[self.carved], self.walk[self.carved + 1]);
+        grid.carve(a, b);
+        self.carved += 1;
+        Some(Change::Carve(a, b))
+    }
+
+    fn start(&mut self, cell: Cell) {
+        self.start = Some(cell);
+    }
+
+    fn cursor(&self) -> Option<Cell> {
+        self.walk.get(self.carved).copied()
+    }
+}
@@ -0,0 +1,437 @@
+use std::{collections::HashMap, path::Path};
+
+use nannou::color::{rgba, Rgba, Srgb};
+
+/// Colour space in which gradient stops are blended
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Blend the gamma encoded values directly
+    Srgb,
+    /// Blend in linear light
+    Linear,
+    /// Blend in the perceptual Oklab space
+    Oklab,
+}
+
+fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert sRGB to Oklab as [L, a, b]
+// The matrices as published with Oklab, more precise than an f32 holds
+#[allow(clippy::excessive_precision)]
+pub fn to_oklab(c: Rgba) -> [f32; 3] {
+    let (r, g, b) = (to_linear(c.red), to_linear(c.green), to_linear(c.blue));
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+/// Convert Oklab given as [L, a, b] to sRGB, clamping colours outside the gamut
+// The inverse matrices, also as published
+#[allow(clippy::excessive_precision)]
+pub fn from_oklab(lab: [f32; 3], alpha: f32) -> Rgba {
+    let l = (lab[0] + 0.3963377774 * lab[1] + 0.2158037573 * lab[2]).powi(3);
+    let m = (lab[0] - 0.1055613458 * lab[1] - 0.0638541728 * lab[2]).powi(3);
+    let s = (lab[0] - 0.0894841775 * lab[1] - 1.2914855480 * lab[2]).powi(3);
+    rgba(
+        from_linear(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+        from_linear(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+        from_linear(-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s),
+        alpha,
+    )
+}
+
+/// Convert one of the named nannou colours, such as PALEGOLDENROD, to an Rgba
+pub fn named_color(c: Srgb<u8>, alpha: f32) -> Rgba {
+    rgba(
+        c.red as f32 / 255.0,
+        c.green as f32 / 255.0,
+        c.blue as f32 / 255.0,
+        alpha,
+    )
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Blend two colours in the given space
+pub fn mix(a: Rgba, b: Rgba, t: f32, interpolation: Interpolation) -> Rgba {
+    let alpha = lerp(a.alpha, b.alpha, t);
+    match interpolation {
+        Interpolation::Srgb => rgba(
+            lerp(a.red, b.red, t),
+            lerp(a.green, b.green, t),
+            lerp(a.blue, b.blue, t),
+            alpha,
+        ),
+        Interpolation::Linear => rgba(
+            from_linear(lerp(to_linear(a.red), to_linear(b.red), t)),
+            from_linear(lerp(to_linear(a.green), to_linear(b.green), t)),
+            from_linear(lerp(to_linear(a.blue), to_linear(b.blue), t)),
+            alpha,
+        ),
+        Interpolation::Oklab => {
+            let (x, y) = (to_oklab(a), to_oklab(b));
+            from_oklab(
+                [
+                    lerp(x[0], y[0], t),
+                    lerp(x[1], y[1], t),
+                    lerp(x[2], y[2], t),
+                ],
+                alpha,
+            )
+        }
+    }
+}
+
+/// Piecewise gradient through colour stops at positions between 0 and 1
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    stops: Vec<(f32, Rgba)>,
+    interpolation: Interpolation,
+}
+
+impl Gradient {
+    pub fn new(mut stops: Vec<(f32, Rgba)>, interpolation: Interpolation) -> Self {
+        assert!(!stops.is_empty(), "a gradient needs at least one stop");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Gradient {
+            stops,
+            interpolation,
+        }
+    }
+
+    /// Spread the colours evenly from 0 to 1
+    pub fn even(colors: &[Rgba], interpolation: Interpolation) -> Self {
+        let n = (colors.len().max(2) - 1) as f32;
+        Gradient::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (i as f32 / n, *c))
+                .collect(),
+            interpolation,
+        )
+    }
+
+    /// The stops in order of position
+    pub fn stops(&self) -> &[(f32, Rgba)] {
+        &self.stops
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    pub fn color(&self, t: f32) -> Rgba {
+        let first = self.stops[0];
+        let last = self.stops[self.stops.len() - 1];
+        // NaN is before every stop rather than between two
+        if t.is_nan() || t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+        let i = self
+            .stops
+            .iter()
+            .position(|s| s.0 > t)
+            .unwrap_or(self.stops.len() - 1);
+        let (p0, c0) = self.stops[i - 1];
+        let (p1, c1) = self.stops[i];
+        mix(c0, c1, (t - p0) / (p1 - p0), self.interpolation)
+    }
+}
+
+/// Procedural palette from Inigo Quilez, each channel is a + b * cos(2pi * (c * t + d))
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cosine {
+    pub a: [f32; 3],
+    pub b: [f32; 3],
+    pub c: [f32; 3],
+    pub d: [f32; 3],
+}
+
+impl Cosine {
+    pub fn color(&self, t: f32) -> Rgba {
+        let channel = |i: usize| {
+            self.a[i] + self.b[i] * (std::f32::consts::TAU * (self.c[i] * t + self.d[i])).cos()
+        };
+        rgba(
+            channel(0).clamp(0.0, 1.0),
+            channel(1).clamp(0.0, 1.0),
+            channel(2).clamp(0.0, 1.0),
+            1.0,
+        )
+    }
+}
+
+/// Maps a scalar, normally between 0 and 1, to a colour
+#[derive(Debug, Clone)]
+pub enum Palette {
+    Solid(Rgba),
+    Gradient(Gradient),
+    Cosine(Cosine),
+}
+
+impl Palette {
+    pub fn color(&self, t: f32) -> Rgba {
+        match self {
+            Palette::Solid(c) => *c,
+            Palette::Gradient(g) => g.color(t),
+            Palette::Cosine(c) => c.color(t),
+        }
+    }
+
+    /// Colour for a value in the range from min to max
+    pub fn map(&self, value: f32, min: f32, max: f32) -> Rgba {
+        if max > min {
+            self.color((value - min) / (max - min))
+        } else {
+            self.color(0.0)
+        }
+    }
+
+    /// The palette named by the PALETTE environment variable, from palettes.txt or else the built
+    /// in palettes, if it is set
+    pub fn from_env() -> Option<Palette> {
+        let name = std::env::var("PALETTE").ok()?;
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("palettes.txt");
+        let mut palettes = load(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            HashMap::new()
+        });
+        let palette = palettes.remove(&name).or_else(|| Palette::named(&name));
+        if palette.is_none() {
+            eprintln!("no palette called {}", name);
+        }
+        palette
+    }
+
+    /// Look up one of the built in palettes by name
+    pub fn named(name: &str) -> Option<Palette> {
+        let cosine = |a, b, c, d| Palette::Cosine(Cosine { a, b, c, d });
+        let gradient =
+            |colors: &[Rgba]| Palette::Gradient(Gradient::even(colors, Interpolation::Oklab));
+        Some(match name {
+            "goldenrod" => Palette::Solid(rgba(0.776, 0.811, 0.266, 1.0)),
+            "rainbow" => cosine([0.5; 3], [0.5; 3], [1.0; 3], [0.0, 0.33, 0.67]),
+            "dusk" => cosine([0.5; 3], [0.5; 3], [1.0; 3], [0.3, 0.2, 0.2]),
+            "moss" => cosine([0.5; 3], [0.5; 3], [1.0, 1.0, 0.5], [0.8, 0.9, 0.3]),
+            "ember" => cosine([0.5; 3], [0.5; 3], [1.0, 0.7, 0.4], [0.0, 0.15, 0.2]),
+            "sunset" => gradient(&[
+                rgba(0.16, 0.09, 0.35, 1.0),
+                rgba(0.82, 0.26, 0.42, 1.0),
+                rgba(0.99, 0.76, 0.39, 1.0),
+            ]),
+            "forest" => gradient(&[
+                rgba(0.08, 0.20, 0.12, 1.0),
+                rgba(0.5, 0.9, 0.2, 1.0),
+                rgba(0.93, 0.96, 0.75, 1.0),
+            ]),
+            "ocean" => gradient(&[
+                rgba(0.02, 0.06, 0.2, 1.0),
+                rgba(0.15, 0.45, 0.7, 1.0),
+                rgba(0.75, 0.95, 0.95, 1.0),
+            ]),
+            _ => return None,
+        })
+    }
+}
+
+fn parse_color(words: &[&str]) -> Result<Rgba, String> {
+    match words {
+        [hex]
+            if hex.starts_with('#')
+                && (hex.len() == 7 || hex.len() == 9)
+                && hex[1..].chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            let byte = |i: usize| {
+                u8::from_str_radix(&hex[i..i + 2], 16)
+                    .map(|b| b as f32 / 255.0)
+                    .map_err(|e| e.to_string())
+            };
+            let alpha = if hex.len() == 9 { byte(7)? } else { 1.0 };
+            Ok(rgba(byte(1)?, byte(3)?, byte(5)?, alpha))
+        }
+        [_, _, _] | [_, _, _, _] => {
+            let v = parse_floats(words)?;
+            Ok(rgba(v[0], v[1], v[2], v.get(3).copied().unwrap_or(1.0)))
+        }
+        _ => Err(format!("unable to read colour from '{}'", words.join(" "))),
+    }
+}
+
+fn parse_floats(words: &[&str]) -> Result<Vec<f32>, String> {
+    words
+        .iter()
+        .map(|w| w.parse::<f32>().map_err(|e| format!("'{}': {}", w, e)))
+        .collect()
+}
+
+/// Read palettes from text.
+///
+/// Each palette starts with its name in brackets. A gradient follows as one stop per line, a
+/// position and then either a hex colour or three or four floats. An optional line reading srgb,
+/// linear or oklab sets how stops are blended, oklab being the default. A cosine palette is a
+/// single line starting with cosine followed by the twelve values a, b, c, d.
+///
+/// ```text
+/// # lines starting with a hash are comments
+/// [sunset]
+/// oklab
+/// 0.0 #2a1759
+/// 1.0 0.99 0.76 0.39
+///
+/// [rainbow]
+/// cosine 0.5 0.5 0.5  0.5 0.5 0.5  1 1 1  0 0.33 0.67
+/// ```
+pub fn parse(text: &str) -> Result<HashMap<String, Palette>, String> {
+    enum Partial {
+        Stops(Vec<(f32, Rgba)>, Interpolation),
+        Cosine(Cosine),
+    }
+
+    fn finish(p: Partial) -> Result<Palette, String> {
+        match p {
+            Partial::Stops(stops, _) if stops.is_empty() => {
+                Err(String::from("palette has no stops"))
+            }
+            Partial::Stops(stops, i) => Ok(Palette::Gradient(Gradient::new(stops, i))),
+            Partial::Cosine(c) => Ok(Palette::Cosine(c)),
+        }
+    }
+
+    let mut palettes = HashMap::new();
+    let mut current: Option<(String, Partial)> = None;
+    for (n, line) in text.lines().enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        let err = |e: String| format!("line {}: {}", n + 1, e);
+        match words[..] {
+            [] => (),
+            [name] if name.len() > 1 && name.starts_with('[') && name.ends_with(']') => {
+                if let Some((name, p)) = current.take() {
+                    palettes.insert(name, finish(p).map_err(err)?);
+                }
+                let name = name[1..name.len() - 1].to_string();
+                current = Some((name, Partial::Stops(Vec::new(), Interpolation::Oklab)));
+            }
+            _ => {
+                let (_, partial) = current
+                    .as_mut()
+                    .ok_or_else(|| err(String::from("expected a [name] before palette data")))?;
+                match (partial, &words[..]) {
+                    (Partial::Stops(_, i), ["srgb"]) => *i = Interpolation::Srgb,
+                    (Partial::Stops(_, i), ["linear"]) => *i = Interpolation::Linear,
+                    (Partial::Stops(_, i), ["oklab"]) => *i = Interpolation::Oklab,
+                    (p @ Partial::Stops(..), ["cosine", values @ ..]) => {
+                        let v = parse_floats(values).map_err(err)?;
+                        if v.len() != 12 {
+                            return Err(err(String::from("cosine palettes need twelve values")));
+                        }
+                        *p = Partial::Cosine(Cosine {
+                            a: [v[0], v[1], v[2]],
+                            b: [v[3], v[4], v[5]],
+                            c: [v[6], v[7], v[8]],
+                            d: [v[9], v[10], v[11]],
+                        });
+                    }
+                    (Partial::Stops(stops, _), [position, color @ ..]) => {
+                        let position = position
+                            .parse::<f32>()
+                            .map_err(|e| err(format!("'{}': {}", position, e)))?;
+                        stops.push((position, parse_color(color).map_err(err)?));
+                    }
+                    _ => return Err(err(format!("unexpected '{}'", line.trim()))),
+                }
+            }
+        }
+    }
+    if let Some((name, p)) = current.take() {
+        palettes.insert(name, finish(p)?);
+    }
+    Ok(palettes)
+}
+
+/// Read every palette in a file, see parse for the format
+pub fn load(path: impl AsRef<Path>) -> Result<HashMap<String, Palette>, String> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_palettes_file() {
+        let palettes = parse(include_str!("../palettes.txt")).unwrap();
+        assert_eq!(palettes.len(), 3);
+        match &palettes["sunset"] {
+            Palette::Gradient(g) => {
+                assert_eq!(g.stops.len(), 3);
+                assert_eq!(g.interpolation, Interpolation::Oklab);
+                let start = g.color(0.0);
+                assert_eq!(start.red, 0x2a as f32 / 255.0);
+                assert_eq!(start.blue, 0x59 as f32 / 255.0);
+            }
+            p => panic!("expected a gradient, got {:?}", p),
+        }
+        assert!(matches!(palettes["neon"], Palette::Cosine(_)));
+    }
+
+    #[test]
+    fn colours() {
+        assert_eq!(parse_color(&["#ff000080"]).unwrap().alpha, 128.0 / 255.0);
+        assert_eq!(parse_color(&["0.1", "0.2", "0.3"]).unwrap().blue, 0.3);
+        // Multibyte characters would split inside a character if sliced into pairs
+        for bad in ["#ff0é0", "#+f+f+f", "#ff00", "red"] {
+            assert!(parse_color(&[bad]).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_palettes() {
+        assert!(parse("0.0 #000000").is_err());
+        assert!(parse("[empty]\n").is_err());
+        assert!(parse("[").is_err());
+        assert!(parse("[c]\ncosine 1 2 3").is_err());
+    }
+
+    #[test]
+    fn gradient_ends_and_nan() {
+        let g = Gradient::even(
+            &[rgba(0.0, 0.0, 0.0, 1.0), rgba(1.0, 1.0, 1.0, 1.0)],
+            Interpolation::Srgb,
+        );
+        assert_eq!(g.color(-1.0).red, 0.0);
+        assert_eq!(g.color(0.5).red, 0.5);
+        assert_eq!(g.color(2.0).red, 1.0);
+        assert_eq!(g.color(f32::NAN).red, 0.0);
+    }
+}
@@ -0,0 +1,1026 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use nannou::{color::rgba, prelude::Vec2};
+
+use crate::{
+    gallery::Exhibit,
+    harmonograph::{self, Harmonograph, Pendulum, Tracer},
+    interference::{Field, Render, Source},
+    l_system::{cursor::Cursor, recipe::Recipe, Action},
+    maze::{mask::Shape, plan::Plan},
+    palette::{Cosine, Gradient, Interpolation, Palette},
+    spline::Smoothing,
+    stroke::{Span, Stroke, Width},
+    style::{Scalar, Style},
+    wave::Waveform,
+};
+
+/// What was run, beyond its name
+#[derive(Debug, Clone)]
+pub enum Params {
+    /// Nothing to record, the sketch has no settings
+    None,
+    LSystem(Recipe),
+    Maze(Plan),
+    Harmonograph(Harmonograph),
+    /// The harmonics drawn and the soundtrack the circles followed, if there was one
+    Wave {
+        waveform: Waveform,
+        harmonics: usize,
+        audio: Option<PathBuf>,
+    },
+    /// The soundtrack the dot followed, if there was one
+    Dot {
+        audio: Option<PathBuf>,
+    },
+    /// The path as resampled and fitted, and how many circles redraw it
+    Fourier {
+        path: Vec<Vec2>,
+        terms: usize,
+    },
+    Interference {
+        field: Field,
+        render: Render,
+        palette: Palette,
+    },
+    /// The caption, what is shown and the style of each layer of each cell. The soundtrack a
+    /// gallery grows to isn't recorded.
+    Gallery {
+        columns: usize,
+        steps_per_update: usize,
+        cells: Vec<(String, Run, Vec<Style>)>,
+    },
+}
+
+/// Description of a run saved beside its captures and exports, so a render found later can be
+/// traced back to what made it and made again
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub sketch: String,
+    pub params: Params,
+    pub window: Option<(u32, u32)>,
+    pub commit: Option<String>,
+    /// Seconds since the Unix epoch
+    pub timestamp: Option<u64>,
+}
+
+impl Run {
+    pub fn new(sketch: &str, params: Params) -> Self {
+        Run {
+            sketch: String::from(sketch),
+            params,
+            window: None,
+            commit: None,
+            timestamp: None,
+        }
+    }
+
+    pub fn window(mut self, width: u32, height: u32) -> Self {
+        self.window = Some((width, height));
+        self
+    }
+
+    /// Record the current git commit and time
+    pub fn stamp(mut self) -> Self {
+        self.commit = git_commit();
+        self.timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs());
+        self
+    }
+
+    /// The run as TOML
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "sketch = {}", quote(&self.sketch)).unwrap();
+        if let Some(t) = self.timestamp {
+            writeln!(out, "timestamp = {}", t).unwrap();
+        }
+        if let Some(commit) = &self.commit {
+            writeln!(out, "commit = {}", quote(commit)).unwrap();
+        }
+        if let Some((w, h)) = self.window {
+            writeln!(out, "window = [{}, {}]", w, h).unwrap();
+        }
+        write_params(&mut out, "", &self.params);
+        out
+    }
+
+    /// Read a run written by to_toml
+    pub fn parse(text: &str) -> Result<Run, String> {
+        let table = parse_table(text)?;
+        let text_of = |key: &str| match table.get(key) {
+            Some(Value::Text(s)) => Ok(s.clone()),
+            _ => Err(format!("missing text value {}", key)),
+        };
+        let params = parse_params(&table, "")?;
+
+        let window = match table.get("window") {
+            Some(Value::List(items)) => match numbers::<u32>(items)?.as_slice() {
+                [w, h] => Some((*w, *h)),
+                _ => return Err(String::from("window needs two numbers")),
+            },
+            _ => None,
+        };
+        Ok(Run {
+            sketch: text_of("sketch")?,
+            params,
+            window,
+            commit: text_of("commit").ok(),
+            timestamp: match table.get("timestamp") {
+                Some(_) => Some(number(&table, "timestamp")?),
+                None => None,
+            },
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_toml())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Run, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        Run::parse(&text)
+    }
+
+    /// Build the drawing again, ready to step through or show in a gallery
+    pub fn exhibit(&self) -> Result<Box<dyn Exhibit>, String> {
+        match &self.params {
+            Params::LSystem(recipe) => Ok(Box::new(recipe.reader())),
+            Params::Maze(plan) => Ok(Box::new(plan.maze()?)),
+            Params::Harmonograph(harmonograph) => Ok(Box::new(Tracer::new(
+                harmonograph.clone(),
+                harmonograph::DURATION,
+                harmonograph::STEPS,
+            ))),
+            Params::Wave { .. }
+            | Params::Dot { .. }
+            | Params::Fourier { .. }
+            | Params::Interference { .. }
+            | Params::Gallery { .. } => Err(format!(
+                "{} isn't a single drawing, replay it with its sketch",
+                self.sketch
+            )),
+            Params::None => Err(format!("{} has nothing to rebuild", self.sketch)),
+        }
+    }
+}
+
+/// Write the params as TOML with each key after the prefix
+fn write_params(out: &mut String, prefix: &str, params: &Params) {
+    match params {
+        Params::None => writeln!(out, "{}kind = \"none\"", prefix).unwrap(),
+        Params::LSystem(recipe) => {
+            writeln!(out, "{}kind = \"l_system\"", prefix).unwrap();
+            writeln!(out, "{}seed = {}", prefix, recipe.seed).unwrap();
+            writeln!(out, "{}axiom = {}", prefix, quote(&recipe.axiom)).unwrap();
+            writeln!(out, "{}depth = {}", prefix, recipe.depth).unwrap();
+            let rules = recipe
+                .rules
+                .iter()
+                .map(|(c, r, _)| quote(&format!("{} -> {}", c, r)))
+                .collect::<Vec<String>>();
+            writeln!(out, "{}rules = [{}]", prefix, rules.join(", ")).unwrap();
+            let weights = recipe
+                .rules
+                .iter()
+                .map(|(_, _, w)| w.to_string())
+                .collect::<Vec<String>>();
+            writeln!(out, "{}weights = [{}]", prefix, weights.join(", ")).unwrap();
+            let mut actions = recipe.actions.iter().collect::<Vec<(&char, &Action)>>();
+            actions.sort_by_key(|(c, _)| **c);
+            let actions = actions
+                .into_iter()
+                .map(|(c, a)| quote(&format!("{} {}", c, a)))
+                .collect::<Vec<String>>();
+            writeln!(out, "{}actions = [{}]", prefix, actions.join(", ")).unwrap();
+            let (p, a) = (recipe.cursor.position(), recipe.cursor.angle());
+            writeln!(
+                out,
+                "{}cursor = [{}, {}, {}, {}]",
+                prefix, p.x, p.y, a.x, a.y
+            )
+            .unwrap();
+        }
+        Params::Maze(plan) => {
+            writeln!(out, "{}kind = \"maze\"", prefix).unwrap();
+            writeln!(out, "{}seed = {}", prefix, plan.seed).unwrap();
+            writeln!(out, "{}algorithm = {}", prefix, quote(&plan.algorithm)).unwrap();
+            writeln!(out, "{}topology = {}", prefix, quote(&plan.topology)).unwrap();
+            let mask = match &plan.shape {
+                Shape::Rect(columns, rows) => format!("\"rect\", {}, {}", columns, rows),
+                Shape::Circle(diameter) => format!("\"circle\", {}", diameter),
+                Shape::Text(text, size) => format!("\"text\", {}, {}", quote(text), size),
+                Shape::Image(path, columns, rows) => format!(
+                    "\"image\", {}, {}, {}",
+                    quote(&path.to_string_lossy()),
+                    columns,
+                    rows
+                ),
+            };
+            writeln!(out, "{}mask = [{}]", prefix, mask).unwrap();
+            writeln!(out, "{}scale = {}", prefix, plan.scale).unwrap();
+        }
+        Params::Harmonograph(harmonograph) => {
+            writeln!(out, "{}kind = \"harmonograph\"", prefix).unwrap();
+            // Frequency, phase, amplitude and decay of each pendulum in turn
+            let axis = |pendulums: &[Pendulum]| {
+                pendulums
+                    .iter()
+                    .flat_map(|p| [p.frequency, p.phase, p.amplitude, p.decay])
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+            writeln!(out, "{}x = [{}]", prefix, axis(&harmonograph.x)).unwrap();
+            writeln!(out, "{}y = [{}]", prefix, axis(&harmonograph.y)).unwrap();
+        }
+        Params::Wave {
+            waveform,
+            harmonics,
+            audio,
+        } => {
+            writeln!(out, "{}kind = \"wave\"", prefix).unwrap();
+            writeln!(out, "{}waveform = {}", prefix, quote(waveform.name())).unwrap();
+            if let Waveform::Custom(coefficients) = waveform {
+                // Sine and cosine coefficients of each harmonic in turn
+                let values = coefficients
+                    .iter()
+                    .flat_map(|(s, c)| [s, c])
+                    .map(|v| v.to_string())
+                    .collect::<Vec<String>>();
+                writeln!(out, "{}coefficients = [{}]", prefix, values.join(", ")).unwrap();
+            }
+            writeln!(out, "{}harmonics = {}", prefix, harmonics).unwrap();
+            write_audio(out, prefix, audio);
+        }
+        Params::Dot { audio } => {
+            writeln!(out, "{}kind = \"dot\"", prefix).unwrap();
+            write_audio(out, prefix, audio);
+        }
+        Params::Fourier { path, terms } => {
+            writeln!(out, "{}kind = \"fourier\"", prefix).unwrap();
+            writeln!(out, "{}terms = {}", prefix, terms).unwrap();
+            let points = path
+                .iter()
+                .flat_map(|p| [p.x, p.y])
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>();
+            writeln!(out, "{}path = [{}]", prefix, points.join(", ")).unwrap();
+        }
+        Params::Interference {
+            field,
+            render,
+            palette,
+        } => {
+            writeln!(out, "{}kind = \"interference\"", prefix).unwrap();
+            writeln!(out, "{}speed = {}", prefix, field.speed).unwrap();
+            // Position, frequency, phase and amplitude of each source in turn
+            let sources = field
+                .sources
+                .iter()
+                .flat_map(|s| {
+                    [
+                        s.position.x,
+                        s.position.y,
+                        s.frequency,
+                        s.phase,
+                        s.amplitude,
+                    ]
+                })
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>();
+            writeln!(out, "{}sources = [{}]", prefix, sources.join(", ")).unwrap();
+            let render = match render {
+                Render::Heightfield => "heightfield",
+                Render::Contours => "contours",
+            };
+            writeln!(out, "{}render = {}", prefix, quote(render)).unwrap();
+            writeln!(out, "{}palette = [{}]", prefix, palette_value(palette)).unwrap();
+        }
+        Params::Gallery {
+            columns,
+            steps_per_update,
+            cells,
+        } => {
+            writeln!(out, "{}kind = \"gallery\"", prefix).unwrap();
+            writeln!(out, "{}columns = {}", prefix, columns).unwrap();
+            writeln!(out, "{}steps_per_update = {}", prefix, steps_per_update).unwrap();
+            // Each cell's keys start with its number, and each style's with the cell's and its own
+            for (i, (caption, run, styles)) in cells.iter().enumerate() {
+                let cell = format!("{}cell.{}.", prefix, i);
+                writeln!(out, "{}caption = {}", cell, quote(caption)).unwrap();
+                writeln!(out, "{}sketch = {}", cell, quote(&run.sketch)).unwrap();
+                write_params(out, &cell, &run.params);
+                for (j, style) in styles.iter().enumerate() {
+                    write_style(out, &format!("{}style.{}.", cell, j), style);
+                }
+            }
+        }
+    }
+}
+
+fn write_style(out: &mut String, prefix: &str, style: &Style) {
+    writeln!(
+        out,
+        "{}palette = [{}]",
+        prefix,
+        palette_value(&style.palette)
+    )
+    .unwrap();
+    let scalar = match style.scalar {
+        Scalar::Position => "position",
+        Scalar::Age => "age",
+        Scalar::Depth => "depth",
+        Scalar::Angle => "angle",
+    };
+    writeln!(out, "{}scalar = {}", prefix, quote(scalar)).unwrap();
+    let stroke = &style.stroke;
+    let width = match stroke.width {
+        Width::Constant(w) => format!("\"constant\", {}", w),
+        Width::Taper(start, end) => format!("\"taper\", {}, {}", start, end),
+        // There's no writing down a function, so a style with one can't be read back
+        Width::Function(_) => String::from("\"function\""),
+    };
+    writeln!(out, "{}width = [{}]", prefix, width).unwrap();
+    let span = match stroke.span {
+        Span::Segment => "segment",
+        Span::Polyline => "polyline",
+        Span::Drawing => "drawing",
+    };
+    writeln!(out, "{}span = {}", prefix, quote(span)).unwrap();
+    let dash = stroke
+        .dash
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>();
+    writeln!(out, "{}dash = [{}]", prefix, dash.join(", ")).unwrap();
+    if let Some(nib) = stroke.nib {
+        writeln!(out, "{}nib = {}", prefix, nib).unwrap();
+    }
+    match style.smoothing {
+        Some(Smoothing::CatmullRom { tension }) => {
+            writeln!(out, "{}smoothing = [\"catmull_rom\", {}]", prefix, tension).unwrap()
+        }
+        Some(Smoothing::Rounded { radius }) => {
+            writeln!(out, "{}smoothing = [\"rounded\", {}]", prefix, radius).unwrap()
+        }
+        None => (),
+    }
+}
+
+/// Read params written by write_params with the prefix
+fn parse_params(table: &HashMap<String, Value>, prefix: &str) -> Result<Params, String> {
+    let key = |key: &str| format!("{}{}", prefix, key);
+    let text_of = |k: &str| match table.get(&key(k)) {
+        Some(Value::Text(s)) => Ok(s.clone()),
+        _ => Err(format!("missing text value {}", key(k))),
+    };
+    let list = |k: &str| match table.get(&key(k)) {
+        Some(Value::List(items)) => Ok(items.clone()),
+        _ => Err(format!("missing list {}", key(k))),
+    };
+
+    Ok(match text_of("kind")?.as_str() {
+        "none" => Params::None,
+        "maze" => {
+            let mask = list("mask")?;
+            let shape = match mask.split_first() {
+                Some((Value::Text(kind), rest)) => match (kind.as_str(), rest) {
+                    ("rect", _) => match numbers::<usize>(rest)?.as_slice() {
+                        [columns, rows] => Shape::Rect(*columns, *rows),
+                        _ => return Err(String::from("rect masks need two numbers")),
+                    },
+                    ("circle", _) => match numbers::<usize>(rest)?.as_slice() {
+                        [diameter] => Shape::Circle(*diameter),
+                        _ => return Err(String::from("circle masks need a diameter")),
+                    },
+                    ("text", [Value::Text(text), rest @ ..]) => {
+                        match numbers::<usize>(rest)?.as_slice() {
+                            [size] => Shape::Text(text.clone(), *size),
+                            _ => return Err(String::from("text masks need a size")),
+                        }
+                    }
+                    ("image", [Value::Text(path), rest @ ..]) => {
+                        match numbers::<usize>(rest)?.as_slice() {
+                            [columns, rows] => Shape::Image(PathBuf::from(path), *columns, *rows),
+                            _ => return Err(String::from("image masks need two numbers")),
+                        }
+                    }
+                    _ => return Err(format!("bad {} mask", kind)),
+                },
+                _ => return Err(String::from("mask needs a kind first")),
+            };
+            Params::Maze(Plan {
+                seed: number(table, &key("seed"))?,
+                algorithm: text_of("algorithm")?,
+                topology: text_of("topology")?,
+                shape,
+                scale: number(table, &key("scale"))?,
+            })
+        }
+        "harmonograph" => {
+            let axis = |key: &str| match numbers::<f32>(&list(key)?)?.as_slice() {
+                values if values.len() % 4 == 0 => Ok(values
+                    .chunks(4)
+                    .map(|v| Pendulum::new(v[0], v[1], v[2], v[3]))
+                    .collect()),
+                _ => Err(format!("{} needs four numbers per pendulum", key)),
+            };
+            Params::Harmonograph(Harmonograph {
+                x: axis("x")?,
+                y: axis("y")?,
+            })
+        }
+        "l_system" => {
+            let rules = texts(&list("rules")?)?;
+            let weights = numbers::<f32>(&list("weights")?)?;
+            if rules.len() != weights.len() {
+                return Err(format!(
+                    "{} rules but {} weights, each rule needs a weight",
+                    rules.len(),
+                    weights.len()
+                ));
+            }
+            let rules = rules
+                .into_iter()
+                .zip(weights)
+                .map(|(rule, w)| match rule.split_once(" -> ") {
+                    Some((c, r)) if c.chars().count() == 1 => {
+                        Ok((c.chars().next().unwrap(), String::from(r), w))
+                    }
+                    _ => Err(format!("bad rule {}", rule)),
+                })
+                .collect::<Result<Vec<(char, String, f32)>, String>>()?;
+            let actions = texts(&list("actions")?)?
+                .into_iter()
+                .map(|entry| {
+                    let mut chars = entry.chars();
+                    let c = chars.next().ok_or("empty action")?;
+                    Ok((c, chars.as_str().parse::<Action>()?))
+                })
+                .collect::<Result<HashMap<char, Action>, String>>()?;
+            let cursor = match numbers::<f32>(&list("cursor")?)?.as_slice() {
+                [x, y, ax, ay] => Cursor::new((*x, *y), (*ax, *ay)),
+                _ => return Err(String::from("cursor needs four numbers")),
+            };
+            Params::LSystem(Recipe {
+                axiom: text_of("axiom")?,
+                rules,
+                depth: number(table, &key("depth"))?,
+                seed: number(table, &key("seed"))?,
+                actions,
+                cursor,
+            })
+        }
+        "wave" => {
+            let waveform = match text_of("waveform")?.as_str() {
+                "custom" => {
+                    let values = numbers::<f32>(&list("coefficients")?)?;
+                    if values.len() % 2 != 0 {
+                        return Err(String::from("coefficients come in pairs"));
+                    }
+                    Waveform::Custom(values.chunks(2).map(|v| (v[0], v[1])).collect())
+                }
+                name => Waveform::named(name).ok_or_else(|| format!("no waveform {}", name))?,
+            };
+            Params::Wave {
+                waveform,
+                harmonics: number(table, &key("harmonics"))?,
+                audio: text_of("audio").ok().map(PathBuf::from),
+            }
+        }
+        "dot" => Params::Dot {
+            audio: text_of("audio").ok().map(PathBuf::from),
+        },
+        "fourier" => {
+            let values = numbers::<f32>(&list("path")?)?;
+            if values.len() % 2 != 0 {
+                return Err(String::from("path needs two numbers per point"));
+            }
+            Params::Fourier {
+                path: values.chunks(2).map(|v| Vec2::new(v[0], v[1])).collect(),
+                terms: number(table, &key("terms"))?,
+            }
+        }
+        "interference" => {
+            let sources = match numbers::<f32>(&list("sources")?)?.as_slice() {
+                values if values.len() % 5 == 0 => values
+                    .chunks(5)
+                    .map(|v| Source::new(Vec2::new(v[0], v[1]), v[2], v[3], v[4]))
+                    .collect(),
+                _ => return Err(String::from("sources need five numbers each")),
+            };
+            let render = match text_of("render")?.as_str() {
+                "heightfield" => Render::Heightfield,
+                "contours" => Render::Contours,
+                render => return Err(format!("unknown render {}", render)),
+            };
+            Params::Interference {
+                field: Field {
+                    sources,
+                    speed: number(table, &key("speed"))?,
+                },
+                render,
+                palette: parse_palette(&list("palette")?)?,
+            }
+        }
+        "gallery" => {
+            let mut cells = Vec::new();
+            while let Ok(sketch) = text_of(&format!("cell.{}.sketch", cells.len())) {
+                let cell = key(&format!("cell.{}.", cells.len()));
+                let mut styles = Vec::new();
+                while table.contains_key(&format!("{}style.{}.palette", cell, styles.len())) {
+                    styles.push(parse_style(
+                        table,
+                        &format!("{}style.{}.", cell, styles.len()),
+                    )?);
+                }
+                let caption = text_of(&format!("cell.{}.caption", cells.len()))?;
+                let run = Run::new(&sketch, parse_params(table, &cell)?);
+                cells.push((caption, run, styles));
+            }
+            Params::Gallery {
+                columns: number(table, &key("columns"))?,
+                steps_per_update: number(table, &key("steps_per_update"))?,
+                cells,
+            }
+        }
+        kind => return Err(format!("unknown kind {}", kind)),
+    })
+}
+
+/// Read a style written by write_style with the prefix
+fn parse_style(table: &HashMap<String, Value>, prefix: &str) -> Result<Style, String> {
+    let get = |key: &str| table.get(&format!("{}{}", prefix, key));
+    let text_of = |key: &str| match get(key) {
+        Some(Value::Text(s)) => Ok(s.as_str()),
+        _ => Err(format!("missing text value {}{}", prefix, key)),
+    };
+    let list = |key: &str| match get(key) {
+        Some(Value::List(items)) => Ok(items.as_slice()),
+        _ => Err(format!("missing list {}{}", prefix, key)),
+    };
+
+    let scalar = match text_of("scalar")? {
+        "position" => Scalar::Position,
+        "age" => Scalar::Age,
+        "depth" => Scalar::Depth,
+        "angle" => Scalar::Angle,
+        scalar => return Err(format!("unknown scalar {}", scalar)),
+    };
+    let width = match list("width")? {
+        [Value::Text(kind), rest @ ..] => match (kind.as_str(), numbers(rest)?.as_slice()) {
+            ("constant", [w]) => Width::Constant(*w),
+            ("taper", [start, end]) => Width::Taper(*start, *end),
+            ("function", _) => {
+                return Err(format!(
+                    "{}width is a function, which can't be rebuilt",
+                    prefix
+                ))
+            }
+            _ => return Err(format!("bad {} width", kind)),
+        },
+        _ => return Err(String::from("width needs a kind first")),
+    };
+    let span = match text_of("span")? {
+        "segment" => Span::Segment,
+        "polyline" => Span::Polyline,
+        "drawing" => Span::Drawing,
+        span => return Err(format!("unknown span {}", span)),
+    };
+    let smoothing = match get("smoothing") {
+        Some(Value::List(items)) => match items.as_slice() {
+            [Value::Text(kind), rest @ ..] => match (kind.as_str(), numbers(rest)?.as_slice()) {
+                ("catmull_rom", [tension]) => Some(Smoothing::CatmullRom { tension: *tension }),
+                ("rounded", [radius]) => Some(Smoothing::Rounded { radius: *radius }),
+                _ => return Err(format!("bad {} smoothing", kind)),
+            },
+            _ => return Err(String::from("smoothing needs a kind first")),
+        },
+        _ => None,
+    };
+    Ok(Style {
+        palette: parse_palette(list("palette")?)?,
+        scalar,
+        stroke: Stroke {
+            width,
+            span,
+            dash: numbers(list("dash")?)?,
+            nib: match get("nib") {
+                Some(_) => Some(number(table, &format!("{}nib", prefix))?),
+                None => None,
+            },
+        },
+        smoothing,
+    })
+}
+
+/// Where the sidecar for a file or capture folder goes
+pub fn sidecar_path(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    if path.is_dir() {
+        path.join("run.toml")
+    } else {
+        path.with_extension("toml")
+    }
+}
+
+/// The commit checked out in the working directory, if it is a git repository
+pub fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    let dirty = Command::new("git")
+        .args(["status", "--porcelain"])
+        .output()
+        .is_ok_and(|o| !o.stdout.is_empty());
+    Some(if dirty { commit + "-dirty" } else { commit })
+}
+
+// The small part of TOML used by runs: one key per line with a string, number or flat list
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    /// Kept as written so large integers survive
+    Number(String),
+    List(Vec<Value>),
+}
+
+/// A palette as the items of a list, its kind first
+fn palette_value(palette: &Palette) -> String {
+    let numbers = |values: &[f32]| {
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    match palette {
+        Palette::Solid(c) => format!("\"solid\", {}", numbers(&[c.red, c.green, c.blue, c.alpha])),
+        Palette::Gradient(g) => {
+            let interpolation = match g.interpolation() {
+                Interpolation::Srgb => "srgb",
+                Interpolation::Linear => "linear",
+                Interpolation::Oklab => "oklab",
+            };
+            // Position and colour of each stop in turn
+            let stops = g
+                .stops()
+                .iter()
+                .flat_map(|(p, c)| [*p, c.red, c.green, c.blue, c.alpha])
+                .collect::<Vec<f32>>();
+            format!(
+                "\"gradient\", {}, {}",
+                quote(interpolation),
+                numbers(&stops)
+            )
+        }
+        Palette::Cosine(c) => format!("\"cosine\", {}", numbers(&[c.a, c.b, c.c, c.d].concat())),
+    }
+}
+
+fn parse_palette(items: &[Value]) -> Result<Palette, String> {
+    let color = |v: &[f32]| rgba(v[0], v[1], v[2], v[3]);
+    match items {
+        [Value::Text(kind), rest @ ..] if kind == "solid" => match numbers(rest)?.as_slice() {
+            v @ [_, _, _, _] => Ok(Palette::Solid(color(v))),
+            _ => Err(String::from("solid palettes need four numbers")),
+        },
+        [Value::Text(kind), Value::Text(interpolation), rest @ ..] if kind == "gradient" => {
+            let interpolation = match interpolation.as_str() {
+                "srgb" => Interpolation::Srgb,
+                "linear" => Interpolation::Linear,
+                "oklab" => Interpolation::Oklab,
+                i => return Err(format!("unknown interpolation {}", i)),
+            };
+            match numbers::<f32>(rest)?.as_slice() {
+                v if !v.is_empty() && v.len() % 5 == 0 => Ok(Palette::Gradient(Gradient::new(
+                    v.chunks(5).map(|s| (s[0], color(&s[1..]))).collect(),
+                    interpolation,
+                ))),
+                _ => Err(String::from("gradient palettes need five numbers per stop")),
+            }
+        }
+        [Value::Text(kind), rest @ ..] if kind == "cosine" => {
+            match numbers::<f32>(rest)?.as_slice() {
+                v if v.len() == 12 => Ok(Palette::Cosine(Cosine {
+                    a: [v[0], v[1], v[2]],
+                    b: [v[3], v[4], v[5]],
+                    c: [v[6], v[7], v[8]],
+                    d: [v[9], v[10], v[11]],
+                })),
+                _ => Err(String::from("cosine palettes need twelve numbers")),
+            }
+        }
+        _ => Err(format!("unknown palette {:?}", items)),
+    }
+}
+
+fn write_audio(out: &mut String, prefix: &str, audio: &Option<PathBuf>) {
+    if let Some(path) = audio {
+        writeln!(out, "{}audio = {}", prefix, quote(&path.to_string_lossy())).unwrap();
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn parse_table(text: &str) -> Result<HashMap<String, Value>, String> {
+    let mut table = HashMap::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected key = value", i + 1))?;
+        let mut chars = value.trim().chars().peekable();
+        let value = parse_value(&mut chars).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        table.insert(String::from(key.trim()), value);
+    }
+    Ok(table)
+}
+
+fn parse_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Value, String> {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    match chars.peek() {
+        Some('"') => {
+            chars.next();
+            let mut s = String::new();
+            loop {
+                match chars.next() {
+                    Some('\\') => s.push(chars.next().ok_or("unfinished escape")?),
+                    Some('"') => return Ok(Value::Text(s)),
+                    Some(c) => s.push(c),
+                    None => return Err(String::from("unfinished string")),
+                }
+            }
+        }
+        Some('[') => {
+            chars.next();
+            let mut items = Vec::new();
+            loop {
+                while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+                if chars.next_if_eq(&']').is_some() {
+                    return Ok(Value::List(items));
+                }
+                if chars.peek().is_none() {
+                    return Err(String::from("unfinished list"));
+                }
+                items.push(parse_value(chars)?);
+            }
+        }
+        Some(_) => {
+            let mut s = String::new();
+            while let Some(c) = chars.next_if(|c| !matches!(c, ',' | ']') && !c.is_whitespace()) {
+                s.push(c);
+            }
+            Ok(Value::Number(s))
+        }
+        None => Err(String::from("missing value")),
+    }
+}
+
+fn number<T: std::str::FromStr>(table: &HashMap<String, Value>, key: &str) -> Result<T, String> {
+    match table.get(key) {
+        Some(Value::Number(n)) => n.parse().map_err(|_| format!("bad {} {}", key, n)),
+        _ => Err(format!("missing number {}", key)),
+    }
+}
+
+fn texts(items: &[Value]) -> Result<Vec<String>, String> {
+    items
+        .iter()
+        .map(|v| match v {
+            Value::Text(s) => Ok(s.clone()),
+            _ => Err(format!("expected text, found {:?}", v)),
+        })
+        .collect()
+}
+
+fn numbers<T: std::str::FromStr>(items: &[Value]) -> Result<Vec<T>, String> {
+    items
+        .iter()
+        .map(|v| match v {
+            Value::Number(n) => n.parse().map_err(|_| format!("bad number {}", n)),
+            _ => Err(format!("expected a number, found {:?}", v)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        interference,
+        l_system::bush,
+        maze::{
+            breadth_first_maze, breadth_first_maze_growing, classic_maze, depth_first_maze,
+            masked_maze, tiled_maze,
+        },
+    };
+
+    #[test]
+    fn l_system_round_trip() {
+        let run = Run::new("bush", Params::LSystem(bush::recipe(26)))
+            .window(1920, 1080)
+            .stamp();
+        let loaded = Run::parse(&run.to_toml()).unwrap();
+        assert_eq!(loaded.to_toml(), run.to_toml());
+        match (&run.params, &loaded.params) {
+            (Params::LSystem(a), Params::LSystem(b)) => {
+                assert_eq!(a.expression(), b.expression());
+            }
+            _ => panic!("expected l_system params"),
+        }
+    }
+
+    #[test]
+    fn rules_and_weights_must_match() {
+        let toml = Run::new("bush", Params::LSystem(bush::recipe(26))).to_toml();
+        let weights = toml.lines().find(|l| l.starts_with("weights")).unwrap();
+        let extra = toml.replace(weights, &weights.replace(']', ", 1]"));
+        assert!(Run::parse(&extra).is_err());
+        let missing = toml.replace(weights, "weights = [1]");
+        assert!(Run::parse(&missing).is_err());
+    }
+
+    #[test]
+    fn maze_round_trip() {
+        let shapes = [
+            Shape::Rect(7, 5),
+            Shape::Circle(9),
+            Shape::Text(String::from("A \"B\""), 1),
+            Shape::Image(PathBuf::from("mask.png"), 48, 32),
+        ];
+        for shape in shapes {
+            let run = Run::new(
+                "masked_maze",
+                Params::Maze(Plan::new(9, "prim", "hex", shape, 4.0)),
+            );
+            let loaded = Run::parse(&run.to_toml()).unwrap();
+            assert_eq!(loaded.to_toml(), run.to_toml());
+        }
+    }
+
+    /// The plan a maze sketch records should carve the same passages as the sketch
+    #[test]
+    fn maze_runs_rebuild_the_maze() {
+        let mazes = [
+            depth_first_maze::seeded(u64::MAX),
+            breadth_first_maze::seeded(3),
+            breadth_first_maze_growing::seeded(3),
+            classic_maze::seeded("wilson", 3).unwrap(),
+            masked_maze::text(3, "OX").unwrap(),
+            tiled_maze::seeded("polar", "kruskal", 3).unwrap(),
+            tiled_maze::seeded("octagon", "eller", 3).unwrap(),
+        ];
+        for mut maze in mazes {
+            let run = maze.capture.run.clone().unwrap();
+            let mut rebuilt = Run::parse(&run.to_toml()).unwrap().exhibit().unwrap();
+            while maze.step() {
+                rebuilt.step();
+            }
+            assert!(!rebuilt.step(), "{}", run.sketch);
+            let ends = |exhibit: &dyn Exhibit| {
+                let layers = exhibit.layers().into_iter();
+                layers
+                    .map(|(segments, _)| segments.iter().map(|s| (s.start(), s.end())).collect())
+                    .collect::<Vec<Vec<_>>>()
+            };
+            assert_eq!(ends(&maze), ends(&rebuilt), "{}", run.sketch);
+        }
+    }
+
+    #[test]
+    fn wave_round_trip() {
+        let waveforms = [
+            (Waveform::Triangle, None),
+            (
+                Waveform::Custom(vec![(1.0, 0.0), (0.0, -0.25), (0.1, 0.1)]),
+                Some(PathBuf::from("beat.wav")),
+            ),
+        ];
+        for (waveform, audio) in waveforms {
+            let run = Run::new(
+                "wave",
+                Params::Wave {
+                    waveform: waveform.clone(),
+                    harmonics: 7,
+                    audio: audio.clone(),
+                },
+            );
+            match Run::parse(&run.to_toml()).unwrap().params {
+                Params::Wave {
+                    waveform: w,
+                    harmonics: 7,
+                    audio: a,
+                } => assert_eq!((w, a), (waveform, audio)),
+                p => panic!("expected wave params, found {:?}", p),
+            }
+        }
+    }
+
+    #[test]
+    fn fourier_round_trip() {
+        let path = (0..50)
+            .map(|i| Vec2::new((i as f32 * 0.3).sin() * 350.0, i as f32 / 7.0))
+            .collect::<Vec<Vec2>>();
+        let run = Run::new(
+            "fourier",
+            Params::Fourier {
+                path: path.clone(),
+                terms: 12,
+            },
+        );
+        match Run::parse(&run.to_toml()).unwrap().params {
+            Params::Fourier { path: p, terms } => assert_eq!((p, terms), (path, 12)),
+            p => panic!("expected fourier params, found {:?}", p),
+        }
+    }
+
+    #[test]
+    fn interference_round_trip() {
+        let palettes = [
+            Palette::named("ocean").unwrap(),
+            Palette::named("rainbow").unwrap(),
+            Palette::named("goldenrod").unwrap(),
+        ];
+        for palette in palettes {
+            let mut field = interference::field();
+            field.sources[1].position = Vec2::new(-3.25, 1e-3);
+            let run = Run::new(
+                "interference",
+                Params::Interference {
+                    field: field.clone(),
+                    render: Render::Contours,
+                    palette: palette.clone(),
+                },
+            );
+            match Run::parse(&run.to_toml()).unwrap().params {
+                Params::Interference {
+                    field: f,
+                    render: Render::Contours,
+                    palette: p,
+                } => {
+                    assert_eq!(f, field);
+                    for t in [0.0, 0.3, 0.5, 1.0] {
+                        assert_eq!(p.color(t), palette.color(t));
+                    }
+                }
+                p => panic!("expected interference params, found {:?}", p),
+            }
+        }
+    }
+
+    #[test]
+    fn style_round_trip() {
+        let style = Style::new(Palette::named("dusk").unwrap(), 2.0)
+            .stroke(Stroke::tapered(4.0, 0.5).dashed(&[3.0, 1.5]).nib(0.25))
+            .smooth(Smoothing::CatmullRom { tension: 0.5 })
+            .scalar(Scalar::Depth);
+        let mut out = String::new();
+        write_style(&mut out, "cell.2.style.0.", &style);
+        let table = parse_table(&out).unwrap();
+        let loaded = parse_style(&table, "cell.2.style.0.").unwrap();
+        let mut again = String::new();
+        write_style(&mut again, "cell.2.style.0.", &loaded);
+        assert_eq!(again, out);
+
+        // A width function can be written but not read back
+        let style = style.stroke(Stroke::function(|t| t * 2.0));
+        let mut out = String::new();
+        write_style(&mut out, "", &style);
+        assert!(parse_style(&parse_table(&out).unwrap(), "").is_err());
+    }
+
+    #[test]
+    fn harmonograph_round_trip() {
+        let run = Run::new(
+            "harmonograph",
+            Params::Harmonograph(Harmonograph::random(7, 300.0)),
+        );
+        let loaded = Run::parse(&run.to_toml()).unwrap();
+        match (&run.params, &loaded.params) {
+            (Params::Harmonograph(a), Params::Harmonograph(b)) => assert_eq!(a, b),
+            _ => panic!("expected harmonograph params"),
+        }
+    }
+}
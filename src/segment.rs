@@ -1,9 +1,14 @@
+use std::ops::Range;
+
 use nannou::{
     draw::{primitive::Line, Drawing},
+    geom::Rect,
     prelude::Vec2,
     Draw,
 };
 
+use crate::transform::Transform;
+
 #[derive(Debug, Copy, Clone)]
 pub struct Segment {
     start: Vec2,
@@ -11,25 +16,169 @@ pub struct Segment {
 }
 
 impl Segment {
+    pub fn start(&self) -> Vec2 {
+        self.start
+    }
+
+    pub fn end(&self) -> Vec2 {
+        self.end
+    }
+
     pub fn scaled(mut self, scale: f32) -> Self {
         self.start *= scale;
         self.end *= scale;
         self
     }
 
+    /// Move the segment by the offset
     pub fn offset(mut self, offset: Vec2) -> Self {
-        self.start -= offset;
-        self.end -= offset;
+        self.start += offset;
+        self.end += offset;
         self
     }
 
-    pub fn line<'a>(&'a self, draw: &'a Draw) -> Drawing<Line> {
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        transform.apply_segment(self)
+    }
+
+    pub fn reversed(&self) -> Self {
+        Segment {
+            start: self.end,
+            end: self.start,
+        }
+    }
+
+    pub fn line<'a>(&'a self, draw: &'a Draw) -> Drawing<'a, Line> {
         draw.line().start(self.start).end(self.end)
     }
 
     pub fn center(&self) -> Vec2 {
         (self.start + self.end) / 2.0
     }
+
+    pub fn length(&self) -> f32 {
+        self.start.distance(self.end)
+    }
+
+    /// Vector from the start to the end
+    pub fn vector(&self) -> Vec2 {
+        self.end - self.start
+    }
+
+    /// Unit vector from the start to the end, zero if the segment has no length
+    pub fn direction(&self) -> Vec2 {
+        self.vector().normalize_or_zero()
+    }
+
+    /// Point a fraction t of the way from the start to the end
+    pub fn point_at(&self, t: f32) -> Vec2 {
+        self.start.lerp(self.end, t)
+    }
+
+    /// Cut the segment in two at a fraction t of the way along it
+    pub fn split(&self, t: f32) -> (Segment, Segment) {
+        let mid = self.point_at(t);
+        (
+            Segment {
+                start: self.start,
+                end: mid,
+            },
+            Segment {
+                start: mid,
+                end: self.end,
+            },
+        )
+    }
+
+    /// Fractions along self and other at which the infinite lines through them cross, None if
+    /// they are parallel
+    pub fn line_intersection_params(&self, other: &Segment) -> Option<(f32, f32)> {
+        let (r, s) = (self.vector(), other.vector());
+        let denom = r.perp_dot(s);
+        if denom.abs() <= f32::EPSILON * r.length() * s.length() {
+            return None;
+        }
+        let q = other.start - self.start;
+        Some((q.perp_dot(s) / denom, q.perp_dot(r) / denom))
+    }
+
+    /// Where the infinite lines through the segments cross
+    pub fn line_intersection(&self, other: &Segment) -> Option<Vec2> {
+        self.line_intersection_params(other)
+            .map(|(t, _)| self.point_at(t))
+    }
+
+    /// Where the segments themselves cross, including at their endpoints
+    pub fn intersection(&self, other: &Segment) -> Option<Vec2> {
+        let (t, u) = self.line_intersection_params(other)?;
+        let range = 0.0..=1.0;
+        (range.contains(&t) && range.contains(&u)).then(|| self.point_at(t))
+    }
+
+    /// Fraction along the segment of the point on it closest to p
+    pub fn closest_param(&self, p: Vec2) -> f32 {
+        let v = self.vector();
+        let len_sq = v.length_squared();
+        if len_sq <= 0.0 {
+            0.0
+        } else {
+            ((p - self.start).dot(v) / len_sq).clamp(0.0, 1.0)
+        }
+    }
+
+    pub fn closest_point(&self, p: Vec2) -> Vec2 {
+        self.point_at(self.closest_param(p))
+    }
+
+    pub fn distance_to(&self, p: Vec2) -> f32 {
+        self.closest_point(p).distance(p)
+    }
+
+    pub fn bounding_box(&self) -> Rect {
+        rect_from_corners(self.start.min(self.end), self.start.max(self.end))
+    }
+}
+
+fn rect_from_corners(min: Vec2, max: Vec2) -> Rect {
+    let size = max - min;
+    let center = (min + max) / 2.0;
+    Rect::from_x_y_w_h(center.x, center.y, size.x, size.y)
+}
+
+/// Smallest rectangle containing every segment, None if there are no segments
+pub fn bounds(segments: &[Segment]) -> Option<Rect> {
+    let first = segments.first()?;
+    let (mut min, mut max) = (first.start.min(first.end), first.start.max(first.end));
+    for s in segments {
+        min = min.min(s.start).min(s.end);
+        max = max.max(s.start).max(s.end);
+    }
+    Some(rect_from_corners(min, max))
+}
+
+/// Ranges of consecutive segments where each starts within tolerance of where the last ended
+pub fn connected_runs(segments: &[Segment], tolerance: f32) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..=segments.len() {
+        if i == segments.len() || segments[i - 1].end.distance(segments[i].start) > tolerance {
+            runs.push(start..i);
+            start = i;
+        }
+    }
+    runs
+}
+
+/// Merge connected segments into polylines, each given as its list of vertices
+pub fn polylines(segments: &[Segment], tolerance: f32) -> Vec<Vec<Vec2>> {
+    connected_runs(segments, tolerance)
+        .into_iter()
+        .map(|run| {
+            let mut points = vec![segments[run.start].start];
+            points.extend(segments[run].iter().map(|s| s.end));
+            points
+        })
+        .collect()
 }
 
 impl From<((i32, i32), (i32, i32))> for Segment {
@@ -48,3 +197,69 @@ impl From<(Vec2, Vec2)> for Segment {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use nannou::prelude::Vec2;
+
+    use super::Segment;
+
+    fn segment(start: (f32, f32), end: (f32, f32)) -> Segment {
+        Segment::from((Vec2::from(start), Vec2::from(end)))
+    }
+
+    #[test]
+    fn crossing_segments_intersect() {
+        let a = segment((0.0, 0.0), (4.0, 4.0));
+        let b = segment((0.0, 4.0), (4.0, 0.0));
+        assert_eq!(a.intersection(&b), Some(Vec2::new(2.0, 2.0)));
+        assert_eq!(a.line_intersection_params(&b), Some((0.5, 0.5)));
+    }
+
+    #[test]
+    fn parallel_segments_dont_intersect() {
+        let a = segment((0.0, 0.0), (4.0, 0.0));
+        let b = segment((0.0, 1.0), (4.0, 1.0));
+        assert_eq!(a.intersection(&b), None);
+        assert_eq!(a.line_intersection(&b), None);
+    }
+
+    #[test]
+    fn segments_touching_at_an_end_intersect() {
+        let a = segment((0.0, 0.0), (4.0, 0.0));
+        let b = segment((4.0, 0.0), (4.0, 3.0));
+        assert_eq!(a.intersection(&b), Some(Vec2::new(4.0, 0.0)));
+    }
+
+    #[test]
+    fn disjoint_segments_dont_intersect() {
+        let a = segment((0.0, 0.0), (1.0, 0.0));
+        let b = segment((3.0, -1.0), (3.0, 1.0));
+        assert_eq!(a.intersection(&b), None);
+        // though the lines through them do
+        assert_eq!(a.line_intersection(&b), Some(Vec2::new(3.0, 0.0)));
+    }
+
+    #[test]
+    fn closest_param_is_clamped_to_the_segment() {
+        let s = segment((0.0, 0.0), (10.0, 0.0));
+        assert_eq!(s.closest_param(Vec2::new(2.5, 7.0)), 0.25);
+        assert_eq!(s.closest_param(Vec2::new(-3.0, 1.0)), 0.0);
+        assert_eq!(s.closest_param(Vec2::new(14.0, -1.0)), 1.0);
+        assert_eq!(s.distance_to(Vec2::new(13.0, 4.0)), 5.0);
+        assert_eq!(
+            segment((1.0, 1.0), (1.0, 1.0)).closest_param(Vec2::ZERO),
+            0.0
+        );
+    }
+
+    #[test]
+    fn split_meets_at_the_fraction() {
+        let (a, b) = segment((0.0, 0.0), (8.0, 4.0)).split(0.25);
+        assert_eq!((a.start(), a.end()), (Vec2::ZERO, Vec2::new(2.0, 1.0)));
+        assert_eq!(
+            (b.start(), b.end()),
+            (Vec2::new(2.0, 1.0), Vec2::new(8.0, 4.0))
+        );
+    }
+}
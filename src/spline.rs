@@ -0,0 +1,281 @@
+use std::{collections::HashMap, fmt::Write};
+
+use nannou::{color::Rgba, prelude::Vec2, Draw};
+
+use crate::segment::{connected_runs, Segment};
+
+// Connected segments are merged into curves when their ends are this close
+const JOIN_TOLERANCE: f32 = 1e-3;
+
+/// How sharp polylines are turned into curves
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Smoothing {
+    /// Cardinal spline through every vertex. A tension of 0 is a Catmull-Rom spline and a
+    /// tension of 1 leaves the polyline unchanged.
+    CatmullRom { tension: f32 },
+    /// Keep the straight runs and replace each corner with a curve starting up to radius away
+    /// from the vertex
+    Rounded { radius: f32 },
+}
+
+/// Cubic Bézier curve along with the index of the segment it replaces
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bezier {
+    pub start: Vec2,
+    pub control1: Vec2,
+    pub control2: Vec2,
+    pub end: Vec2,
+    pub source: usize,
+}
+
+impl Bezier {
+    /// A straight line written as a cubic
+    fn line(start: Vec2, end: Vec2, source: usize) -> Self {
+        Bezier {
+            start,
+            control1: start.lerp(end, 1.0 / 3.0),
+            control2: start.lerp(end, 2.0 / 3.0),
+            end,
+            source,
+        }
+    }
+
+    /// The quadratic curve with the given control point, raised to a cubic
+    fn quadratic(start: Vec2, control: Vec2, end: Vec2, source: usize) -> Self {
+        Bezier {
+            start,
+            control1: start.lerp(control, 2.0 / 3.0),
+            control2: end.lerp(control, 2.0 / 3.0),
+            end,
+            source,
+        }
+    }
+
+    pub fn point_at(&self, t: f32) -> Vec2 {
+        let u = 1.0 - t;
+        self.start * (u * u * u)
+            + self.control1 * (3.0 * u * u * t)
+            + self.control2 * (3.0 * u * t * t)
+            + self.end * (t * t * t)
+    }
+}
+
+/// A smooth curve made of Béziers joined end to end
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve {
+    pub pieces: Vec<Bezier>,
+}
+
+impl Curve {
+    /// Points along the curve with the given number of steps per Bézier
+    pub fn points(&self, samples: usize) -> Vec<Vec2> {
+        let samples = samples.max(1);
+        let mut points = Vec::with_capacity(self.pieces.len() * samples + 1);
+        if let Some(first) = self.pieces.first() {
+            points.push(first.start);
+        }
+        for piece in self.pieces.iter() {
+            points.extend((1..=samples).map(|i| piece.point_at(i as f32 / samples as f32)));
+        }
+        points
+    }
+
+    pub fn draw(&self, draw: &Draw, weight: f32, color: Rgba, samples: usize) {
+        draw.polyline()
+            .weight(weight)
+            .points_colored(self.points(samples).into_iter().map(|p| (p, color)));
+    }
+
+    /// The curve as path data for an SVG path element, with y flipped to point down
+    pub fn svg_path(&self) -> String {
+        let mut d = String::new();
+        if let Some(first) = self.pieces.first() {
+            write!(d, "M{} {}", first.start.x, -first.start.y).unwrap();
+        }
+        for p in self.pieces.iter() {
+            write!(
+                d,
+                " C{} {} {} {} {} {}",
+                p.control1.x, -p.control1.y, p.control2.x, -p.control2.y, p.end.x, -p.end.y
+            )
+            .unwrap();
+        }
+        d
+    }
+
+    /// Every point defining the curve, the curve lies within their bounds
+    pub fn control_points(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.pieces
+            .iter()
+            .flat_map(|p| [p.start, p.control1, p.control2, p.end])
+    }
+}
+
+fn key(p: Vec2) -> (i64, i64) {
+    (
+        (p.x / JOIN_TOLERANCE).round() as i64,
+        (p.y / JOIN_TOLERANCE).round() as i64,
+    )
+}
+
+/// Smooth each run of connected segments into a curve.
+///
+/// Branches, such as those made by PushCursor and PopCursor, start new runs. When a run starts
+/// from a vertex of an earlier run it leaves in the direction the earlier run arrived from, so
+/// a stem continues smoothly into each of its branches.
+pub fn smooth(segments: &[Segment], smoothing: Smoothing) -> Vec<Curve> {
+    // For each vertex, the point the path came from when first reaching it
+    let mut arrived_from: HashMap<(i64, i64), Vec2> = HashMap::new();
+    let mut curves = Vec::new();
+
+    for run in connected_runs(segments, JOIN_TOLERANCE) {
+        let mut points = vec![segments[run.start].start()];
+        points.extend(segments[run.clone()].iter().map(|s| s.end()));
+        let before = arrived_from.get(&key(points[0])).copied();
+        for w in points.windows(2) {
+            arrived_from.entry(key(w[1])).or_insert(w[0]);
+        }
+
+        let pieces = match smoothing {
+            Smoothing::CatmullRom { tension } => catmull_rom(&points, before, tension, run.start),
+            Smoothing::Rounded { radius } => rounded(&points, before, radius, run.start),
+        };
+        curves.push(Curve { pieces });
+    }
+    curves
+}
+
+fn catmull_rom(points: &[Vec2], before: Option<Vec2>, tension: f32, first: usize) -> Vec<Bezier> {
+    let n = points.len();
+    // Phantom points beyond the ends keep the end tangents along the first and last segments
+    let start = before.unwrap_or(points[0] * 2.0 - points[1]);
+    let end = points[n - 1] * 2.0 - points[n - 2];
+    let at = |i: isize| {
+        if i < 0 {
+            start
+        } else if i as usize >= n {
+            end
+        } else {
+            points[i as usize]
+        }
+    };
+    let scale = (1.0 - tension) / 6.0;
+    (0..n - 1)
+        .map(|i| {
+            let i = i as isize;
+            let (p0, p1, p2, p3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+            Bezier {
+                start: p1,
+                control1: p1 + (p2 - p0) * scale,
+                control2: p2 - (p3 - p1) * scale,
+                end: p2,
+                source: first + i as usize,
+            }
+        })
+        .collect()
+}
+
+fn rounded(points: &[Vec2], before: Option<Vec2>, radius: f32, first: usize) -> Vec<Bezier> {
+    let n = points.len();
+    let mut pieces = Vec::new();
+    // Distance cut from the start and end of each segment to make room for the corners
+    let cut = |a: Vec2, b: Vec2, c: Vec2| radius.min(a.distance(b) / 2.0).min(b.distance(c) / 2.0);
+
+    // A branch leaving its parent at an angle gets a rounded corner at its start too
+    let mut from = points[0];
+    if let Some(b) = before {
+        let r = cut(b, points[0], points[1]);
+        let exit = points[0] + (points[1] - points[0]).normalize_or_zero() * r;
+        let entry = points[0] + (b - points[0]).normalize_or_zero() * r;
+        pieces.push(Bezier::quadratic(entry, points[0], exit, first));
+        from = exit;
+    }
+
+    for i in 1..n {
+        let corner = points[i];
+        if i + 1 < n {
+            let r = cut(points[i - 1], corner, points[i + 1]);
+            let entry = corner + (points[i - 1] - corner).normalize_or_zero() * r;
+            let exit = corner + (points[i + 1] - corner).normalize_or_zero() * r;
+            pieces.push(Bezier::line(from, entry, first + i - 1));
+            pieces.push(Bezier::quadratic(entry, corner, exit, first + i - 1));
+            from = exit;
+        } else {
+            pieces.push(Bezier::line(from, corner, first + i - 1));
+        }
+    }
+    pieces
+}
+
+/// Smooth the segments and sample the curves back into short segments, so they can be drawn
+/// with any Style. Depths, if given, follow the segment each piece came from.
+pub fn sampled(
+    segments: &[Segment],
+    depths: &[usize],
+    smoothing: Smoothing,
+    samples: usize,
+) -> (Vec<Segment>, Vec<usize>) {
+    let samples = samples.max(1);
+    let mut out = Vec::new();
+    let mut out_depths = Vec::new();
+    for curve in smooth(segments, smoothing) {
+        for piece in curve.pieces.iter() {
+            let mut last = piece.start;
+            for i in 1..=samples {
+                let p = piece.point_at(i as f32 / samples as f32);
+                out.push(Segment::from((last, p)));
+                out_depths.push(depths.get(piece.source).copied().unwrap_or(0));
+                last = p;
+            }
+        }
+    }
+    (out, out_depths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(points: &[(f32, f32)]) -> Vec<Segment> {
+        points
+            .windows(2)
+            .map(|w| Segment::from((Vec2::new(w[0].0, w[0].1), Vec2::new(w[1].0, w[1].1))))
+            .collect()
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_the_vertices() {
+        let segments = path(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (20.0, 15.0)]);
+        let curves = smooth(&segments, Smoothing::CatmullRom { tension: 0.0 });
+        assert_eq!(curves.len(), 1);
+        let pieces = &curves[0].pieces;
+        assert_eq!(pieces.len(), segments.len());
+        for (piece, segment) in pieces.iter().zip(segments.iter()) {
+            assert!(piece.point_at(0.0).distance(segment.start()) < 1e-4);
+            assert!(piece.point_at(1.0).distance(segment.end()) < 1e-4);
+        }
+        // The ends leave along the first and last segments
+        let first = (pieces[0].control1 - pieces[0].start).normalize();
+        assert!(first.distance(Vec2::X) < 1e-4);
+        let last = pieces[2].end - pieces[2].control2;
+        assert!(last.normalize().distance(Vec2::new(2.0, 1.0).normalize()) < 1e-4);
+    }
+
+    #[test]
+    fn full_tension_keeps_the_polyline() {
+        let segments = path(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        let curves = smooth(&segments, Smoothing::CatmullRom { tension: 1.0 });
+        let middle = curves[0].pieces[0].point_at(0.5);
+        assert!(middle.distance(Vec2::new(5.0, 0.0)) < 1e-4);
+    }
+
+    #[test]
+    fn rounded_keeps_the_ends() {
+        let segments = path(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        let (sampled, _) = sampled(&segments, &[], Smoothing::Rounded { radius: 2.0 }, 4);
+        assert_eq!(sampled.first().unwrap().start(), Vec2::ZERO);
+        assert_eq!(sampled.last().unwrap().end(), Vec2::new(10.0, 10.0));
+        // Two straight runs and a corner between them
+        assert_eq!(sampled.len(), 3 * 4);
+    }
+}
@@ -0,0 +1,353 @@
+use nannou::{color::Rgba, prelude::Vec2, Draw};
+
+use crate::segment::{connected_runs, Segment};
+
+/// Width of a stroke at a fraction t of the way along its span
+#[derive(Debug, Copy, Clone)]
+pub enum Width {
+    Constant(f32),
+    /// Change linearly from the first width to the second
+    Taper(f32, f32),
+    /// Any function of t
+    Function(fn(f32) -> f32),
+}
+
+impl Width {
+    pub fn at(&self, t: f32) -> f32 {
+        match self {
+            Width::Constant(w) => *w,
+            Width::Taper(start, end) => start + (end - start) * t,
+            Width::Function(f) => f(t),
+        }
+    }
+}
+
+/// What t in the width runs over
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Span {
+    /// From the start to the end of each segment
+    Segment,
+    /// Along each run of connected segments
+    Polyline,
+    /// Across all the segments in the order they were drawn, each having a single width
+    Drawing,
+}
+
+/// How segments are turned into shapes
+#[derive(Debug, Clone)]
+pub struct Stroke {
+    pub width: Width,
+    pub span: Span,
+    /// Alternating lengths of dashes and gaps, empty for a solid line
+    pub dash: Vec<f32>,
+    /// Angle in radians of a calligraphic nib, None for a round pen
+    pub nib: Option<f32>,
+}
+
+/// An inked part of a segment
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Piece {
+    /// Index of the segment it's part of
+    index: usize,
+    start: Vec2,
+    end: Vec2,
+    widths: (f32, f32),
+}
+
+// Connected segments are merged into polylines when their ends are this close
+const JOIN_TOLERANCE: f32 = 1e-3;
+
+impl Stroke {
+    pub fn solid(width: f32) -> Self {
+        Stroke {
+            width: Width::Constant(width),
+            span: Span::Segment,
+            dash: Vec::new(),
+            nib: None,
+        }
+    }
+
+    /// Narrow from the start width to the end width along each polyline
+    pub fn tapered(start: f32, end: f32) -> Self {
+        Stroke {
+            width: Width::Taper(start, end),
+            span: Span::Polyline,
+            ..Stroke::solid(start)
+        }
+    }
+
+    /// Every segment has one width, changing from the first segment drawn to the last. Good for
+    /// showing the age of the segments in a growing drawing.
+    pub fn ramp(first: f32, last: f32) -> Self {
+        Stroke {
+            width: Width::Taper(first, last),
+            span: Span::Drawing,
+            ..Stroke::solid(first)
+        }
+    }
+
+    /// Width given by a function of the fraction along each polyline
+    pub fn function(f: fn(f32) -> f32) -> Self {
+        Stroke {
+            width: Width::Function(f),
+            span: Span::Polyline,
+            ..Stroke::solid(1.0)
+        }
+    }
+
+    pub fn span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Alternating dash and gap lengths, measured along each polyline
+    pub fn dashed(mut self, pattern: &[f32]) -> Self {
+        // Like SVG an odd pattern is repeated to make it even
+        self.dash = pattern.to_vec();
+        if self.dash.len() % 2 == 1 {
+            self.dash.extend_from_slice(pattern);
+        }
+        self
+    }
+
+    /// Draw with a flat nib held at the given angle so the width depends on direction
+    pub fn nib(mut self, radians: f32) -> Self {
+        self.nib = Some(radians);
+        self
+    }
+
+    /// Parts of the distance range from a to b, measured along a polyline, that are inked
+    fn dashes(&self, a: f32, b: f32) -> Vec<(f32, f32)> {
+        let period: f32 = self.dash.iter().sum();
+        if self.dash.is_empty() || period <= 0.0 {
+            return vec![(a, b)];
+        }
+        let mut out = Vec::new();
+        let mut pos = a;
+        while pos < b {
+            let mut phase = pos % period;
+            let mut i = 0;
+            while phase >= self.dash[i] {
+                phase -= self.dash[i];
+                i = (i + 1) % self.dash.len();
+            }
+            let end = b.min(pos + self.dash[i] - phase);
+            if i % 2 == 0 {
+                out.push((pos, end));
+            }
+            // Guard against lengths too small to advance the position
+            pos = end.max(pos + period * 1e-4);
+        }
+        out
+    }
+
+    /// Half the unit width across a piece running from start to end: along the nib, or square to
+    /// the piece for a round pen
+    fn across(&self, start: Vec2, end: Vec2) -> Vec2 {
+        match self.nib {
+            Some(angle) => Vec2::new(angle.cos(), angle.sin()) * 0.5,
+            None => (end - start).normalize_or_zero().perp() * 0.5,
+        }
+    }
+
+    /// Corners of a piece whose width changes from its start to its end
+    fn outline(&self, piece: &Piece) -> [Vec2; 4] {
+        let across = self.across(piece.start, piece.end);
+        [
+            piece.start + across * piece.widths.0,
+            piece.end + across * piece.widths.1,
+            piece.end - across * piece.widths.1,
+            piece.start - across * piece.widths.0,
+        ]
+    }
+
+    fn piece(&self, draw: &Draw, piece: &Piece, color: Rgba) {
+        let (start, end, widths) = (piece.start, piece.end, piece.widths);
+        if self.nib.is_none() && (widths.0 - widths.1).abs() < 1e-3 {
+            draw.line()
+                .start(start)
+                .end(end)
+                .weight(widths.0)
+                .color(color)
+                .caps_round();
+        } else {
+            draw.polygon().color(color).points(self.outline(piece));
+            if self.nib.is_none() {
+                draw.ellipse().color(color).xy(start).radius(widths.0 / 2.0);
+                draw.ellipse().color(color).xy(end).radius(widths.1 / 2.0);
+            }
+        }
+    }
+
+    /// The inked pieces of the segments, with their widths at each end
+    fn pieces(&self, segments: &[Segment]) -> Vec<Piece> {
+        let last = (segments.len().max(2) - 1) as f32;
+        let mut pieces = Vec::new();
+        for run in connected_runs(segments, JOIN_TOLERANCE) {
+            let total: f32 = segments[run.clone()].iter().map(|s| s.length()).sum();
+            let mut travelled = 0.0;
+            for i in run {
+                let segment = segments[i];
+                let length = segment.length();
+                let t = |d: f32| match self.span {
+                    Span::Segment if length > 0.0 => d / length,
+                    Span::Polyline if total > 0.0 => (travelled + d) / total,
+                    Span::Drawing => i as f32 / last,
+                    _ => 0.0,
+                };
+                for (a, b) in self.dashes(travelled, travelled + length) {
+                    let (a, b) = (a - travelled, b - travelled);
+                    let point = |d: f32| {
+                        if length > 0.0 {
+                            segment.start().lerp(segment.end(), d / length)
+                        } else {
+                            segment.start()
+                        }
+                    };
+                    pieces.push(Piece {
+                        index: i,
+                        start: point(a),
+                        end: point(b),
+                        widths: (self.width.at(t(a)), self.width.at(t(b))),
+                    });
+                }
+                travelled += length;
+            }
+        }
+        pieces
+    }
+
+    /// Draw the segments, colouring each by its index
+    pub fn draw(&self, draw: &Draw, segments: &[Segment], color: impl Fn(usize) -> Rgba) {
+        for piece in self.pieces(segments) {
+            self.piece(draw, &piece, color(piece.index));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::prelude::Vec2;
+
+    use super::{Piece, Span, Stroke, Width};
+    use crate::segment::Segment;
+
+    fn segment(start: (f32, f32), end: (f32, f32)) -> Segment {
+        Segment::from((Vec2::from(start), Vec2::from(end)))
+    }
+
+    // Two connected segments, 10 and 20 long, then one of 10 on its own
+    fn drawing() -> Vec<Segment> {
+        vec![
+            segment((0.0, 0.0), (10.0, 0.0)),
+            segment((10.0, 0.0), (30.0, 0.0)),
+            segment((0.0, 10.0), (10.0, 10.0)),
+        ]
+    }
+
+    fn widths(stroke: &Stroke) -> Vec<(f32, f32)> {
+        stroke
+            .pieces(&drawing())
+            .iter()
+            .map(|piece| piece.widths)
+            .collect()
+    }
+
+    #[test]
+    fn solid_stroke_inks_everything() {
+        assert_eq!(Stroke::solid(1.0).dashes(3.0, 8.0), vec![(3.0, 8.0)]);
+    }
+
+    #[test]
+    fn odd_dash_pattern_is_repeated() {
+        let stroke = Stroke::solid(1.0).dashed(&[2.0, 1.0, 3.0]);
+        assert_eq!(stroke.dash, vec![2.0, 1.0, 3.0, 2.0, 1.0, 3.0]);
+        // Dash 2, gap 1, dash 3, gap 2, dash 1, gap 3
+        assert_eq!(
+            stroke.dashes(0.0, 12.0),
+            vec![(0.0, 2.0), (3.0, 6.0), (8.0, 9.0)]
+        );
+        assert_eq!(stroke.dashes(10.0, 15.0), vec![(12.0, 14.0)]);
+    }
+
+    #[test]
+    fn dashes_carry_on_across_connected_segments() {
+        let stroke = Stroke::solid(1.0).dashed(&[4.0, 4.0]);
+        let pieces: Vec<_> = stroke
+            .pieces(&drawing())
+            .iter()
+            .map(|piece| (piece.index, piece.start.x, piece.end.x))
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                (0, 0.0, 4.0),
+                (0, 8.0, 10.0),
+                (1, 10.0, 12.0),
+                (1, 16.0, 20.0),
+                (1, 24.0, 28.0),
+                (2, 0.0, 4.0),
+                (2, 8.0, 10.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn width_ends() {
+        assert_eq!(Width::Constant(2.0).at(0.0), 2.0);
+        assert_eq!(Width::Constant(2.0).at(1.0), 2.0);
+        assert_eq!(Width::Taper(4.0, 1.0).at(0.0), 4.0);
+        assert_eq!(Width::Taper(4.0, 1.0).at(1.0), 1.0);
+        assert_eq!(Width::Function(|t| t * t + 1.0).at(1.0), 2.0);
+    }
+
+    #[test]
+    fn taper_along_each_segment() {
+        let stroke = Stroke::tapered(4.0, 1.0).span(Span::Segment);
+        assert_eq!(widths(&stroke), vec![(4.0, 1.0); 3]);
+    }
+
+    #[test]
+    fn taper_along_each_polyline() {
+        let stroke = Stroke::tapered(4.0, 1.0);
+        assert_eq!(stroke.span, Span::Polyline);
+        assert_eq!(widths(&stroke), vec![(4.0, 3.0), (3.0, 1.0), (4.0, 1.0)]);
+    }
+
+    #[test]
+    fn ramp_across_the_drawing() {
+        let stroke = Stroke::ramp(4.0, 1.0);
+        assert_eq!(stroke.span, Span::Drawing);
+        assert_eq!(widths(&stroke), vec![(4.0, 4.0), (2.5, 2.5), (1.0, 1.0)]);
+    }
+
+    #[test]
+    fn nib_width_depends_on_direction() {
+        let stroke = Stroke::solid(2.0).nib(0.0);
+        let piece = |end: (f32, f32)| Piece {
+            index: 0,
+            start: Vec2::ZERO,
+            end: end.into(),
+            widths: (2.0, 2.0),
+        };
+        // Across a horizontal nib a vertical stroke is at its widest
+        assert_eq!(
+            stroke.outline(&piece((0.0, 10.0))),
+            [
+                Vec2::new(1.0, 0.0),
+                Vec2::new(1.0, 10.0),
+                Vec2::new(-1.0, 10.0),
+                Vec2::new(-1.0, 0.0),
+            ]
+        );
+        // and a horizontal one has no width at all
+        assert!(stroke
+            .outline(&piece((10.0, 0.0)))
+            .iter()
+            .all(|corner| corner.y == 0.0));
+        // A round pen is as wide whichever way it goes
+        let round = Stroke::solid(2.0).outline(&piece((10.0, 0.0)));
+        assert_eq!(round[0], Vec2::new(0.0, 1.0));
+        assert_eq!(round[3], Vec2::new(0.0, -1.0));
+    }
+}
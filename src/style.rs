@@ -0,0 +1,107 @@
+use nannou::Draw;
+
+use crate::{
+    l_system::SymbolReader,
+    palette::Palette,
+    segment::Segment,
+    spline::{sampled, Smoothing},
+    stroke::Stroke,
+};
+
+// Number of straight pieces each curve of a smoothed drawing is broken into
+const CURVE_SAMPLES: usize = 8;
+
+/// Quantity used to pick each segment's colour from the palette
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scalar {
+    /// Order the segments were drawn in, the first at 0 and the last at 1
+    Position,
+    /// Reverse of Position so the newest segment is at 0
+    Age,
+    /// Depth of the cursor stack when the segment was drawn, scaled by the deepest
+    Depth,
+    /// Direction of the segment with one full turn covering 0 to 1
+    Angle,
+}
+
+/// How to colour and stroke a collection of segments
+#[derive(Debug, Clone)]
+pub struct Style {
+    pub palette: Palette,
+    pub scalar: Scalar,
+    pub stroke: Stroke,
+    /// Turn connected segments into curves before drawing
+    pub smoothing: Option<Smoothing>,
+}
+
+impl Style {
+    /// Solid strokes of the given weight
+    pub fn new(palette: Palette, weight: f32) -> Self {
+        Style {
+            palette,
+            scalar: Scalar::Position,
+            stroke: Stroke::solid(weight),
+            smoothing: None,
+        }
+    }
+
+    pub fn stroke(mut self, stroke: Stroke) -> Self {
+        self.stroke = stroke;
+        self
+    }
+
+    pub fn smooth(mut self, smoothing: Smoothing) -> Self {
+        self.smoothing = Some(smoothing);
+        self
+    }
+
+    pub fn scalar(mut self, scalar: Scalar) -> Self {
+        self.scalar = scalar;
+        self
+    }
+
+    /// Palette position of each segment. Depths may be empty if there is no cursor stack.
+    pub fn values(&self, segments: &[Segment], depths: &[usize]) -> Vec<f32> {
+        let last = (segments.len().max(2) - 1) as f32;
+        match self.scalar {
+            Scalar::Position => (0..segments.len()).map(|i| i as f32 / last).collect(),
+            Scalar::Age => (0..segments.len()).map(|i| 1.0 - i as f32 / last).collect(),
+            Scalar::Depth => {
+                let deepest = depths.iter().copied().max().unwrap_or(0).max(1) as f32;
+                (0..segments.len())
+                    .map(|i| depths.get(i).copied().unwrap_or(0) as f32 / deepest)
+                    .collect()
+            }
+            Scalar::Angle => segments
+                .iter()
+                .map(|s| {
+                    let d = s.end() - s.start();
+                    (d.y.atan2(d.x) / std::f32::consts::TAU).rem_euclid(1.0)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn draw(&self, draw: &Draw, segments: &[Segment]) {
+        self.draw_with_depths(draw, segments, &[])
+    }
+
+    pub fn draw_with_depths(&self, draw: &Draw, segments: &[Segment], depths: &[usize]) {
+        if let Some(smoothing) = self.smoothing {
+            let (segments, depths) = sampled(segments, depths, smoothing, CURVE_SAMPLES);
+            let plain = Style {
+                smoothing: None,
+                ..self.clone()
+            };
+            return plain.draw_with_depths(draw, &segments, &depths);
+        }
+        let values = self.values(segments, depths);
+        self.stroke
+            .draw(draw, segments, |i| self.palette.color(values[i]));
+    }
+
+    /// Draw everything the reader has produced so far
+    pub fn draw_reader(&self, draw: &Draw, reader: &SymbolReader) {
+        self.draw_with_depths(draw, &reader.segments, &reader.depths)
+    }
+}
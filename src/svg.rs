@@ -0,0 +1,390 @@
+use std::{fmt::Write, path::Path};
+
+use nannou::prelude::Vec2;
+
+use crate::{
+    segment::{self, Segment},
+    spline::{Bezier, Curve},
+};
+
+/// A group of segments and curves drawn with the same stroke
+pub struct Layer<'a> {
+    pub segments: &'a [Segment],
+    pub curves: &'a [Curve],
+    pub stroke: &'a str,
+    pub width: f32,
+}
+
+impl<'a> Layer<'a> {
+    pub fn new(segments: &'a [Segment], stroke: &'a str, width: f32) -> Self {
+        Layer {
+            segments,
+            curves: &[],
+            stroke,
+            width,
+        }
+    }
+
+    pub fn curves(curves: &'a [Curve], stroke: &'a str, width: f32) -> Self {
+        Layer {
+            segments: &[],
+            curves,
+            stroke,
+            width,
+        }
+    }
+}
+
+// SVG has y pointing down while nannou has y pointing up
+fn flip(p: Vec2) -> Vec2 {
+    Vec2::new(p.x, -p.y)
+}
+
+/// Bounds of all the layers as (min, max) in SVG coordinates
+fn bounds(layers: &[Layer]) -> (Vec2, Vec2) {
+    // Curves lie within the bounds of their control points
+    let segments = layers
+        .iter()
+        .flat_map(|l| {
+            l.segments.iter().copied().chain(
+                l.curves
+                    .iter()
+                    .flat_map(|c| c.control_points())
+                    .map(|p| Segment::from((p, p))),
+            )
+        })
+        .collect::<Vec<Segment>>();
+    match segment::bounds(&segments) {
+        // Flipping y swaps the roles of top and bottom
+        Some(r) => (
+            Vec2::new(r.left(), -r.top()),
+            Vec2::new(r.right(), -r.bottom()),
+        ),
+        None => (Vec2::ZERO, Vec2::ZERO),
+    }
+}
+
+/// Create an SVG document containing every layer, with padding around the drawing
+pub fn document(layers: &[Layer], padding: f32) -> String {
+    let (min, max) = bounds(layers);
+    let size = max - min + Vec2::splat(padding * 2.0);
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min.x - padding,
+        min.y - padding,
+        size.x,
+        size.y
+    )
+    .unwrap();
+    for layer in layers {
+        writeln!(
+            out,
+            r#"<g stroke="{}" stroke-width="{}" stroke-linecap="round" fill="none">"#,
+            layer.stroke, layer.width
+        )
+        .unwrap();
+        for segment in layer.segments {
+            let (a, b) = (flip(segment.start()), flip(segment.end()));
+            writeln!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                a.x, a.y, b.x, b.y
+            )
+            .unwrap();
+        }
+        for curve in layer.curves {
+            writeln!(out, r#"<path d="{}"/>"#, curve.svg_path()).unwrap();
+        }
+        writeln!(out, "</g>").unwrap();
+    }
+    writeln!(out, "</svg>").unwrap();
+    out
+}
+
+pub fn save(path: impl AsRef<Path>, layers: &[Layer], padding: f32) -> std::io::Result<()> {
+    std::fs::write(path, document(layers, padding))
+}
+
+fn path_tokens(d: &str) -> Result<Vec<PathToken>, String> {
+    let mut tokens = Vec::new();
+    let chars = d.chars().collect::<Vec<char>>();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' {
+            i += 1;
+        } else if c.is_ascii_alphabetic() && c != 'e' && c != 'E' {
+            tokens.push(PathToken::Command(c));
+            i += 1;
+        } else {
+            // A number is an optional sign, digits with at most one point, and an exponent
+            let start = i;
+            if chars[i] == '-' || chars[i] == '+' {
+                i += 1;
+            }
+            let mut seen_point = false;
+            while i < chars.len() && (chars[i].is_ascii_digit() || (chars[i] == '.' && !seen_point))
+            {
+                seen_point |= chars[i] == '.';
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+                if i < chars.len() && (chars[i] == '-' || chars[i] == '+') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text = chars[start..i].iter().collect::<String>();
+            let n = text
+                .parse::<f32>()
+                .map_err(|_| format!("bad number {:?} in path at {}", text, start))?;
+            tokens.push(PathToken::Number(n));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PathToken {
+    Command(char),
+    Number(f32),
+}
+
+/// Points along each subpath of SVG path data, in nannou coordinates with y pointing up.
+/// Curves are broken into the given number of straight steps. Arcs aren't supported.
+pub fn parse_path(d: &str, samples: usize) -> Result<Vec<Vec<Vec2>>, String> {
+    let samples = samples.max(1);
+    let tokens = path_tokens(d)?;
+    let mut subpaths: Vec<Vec<Vec2>> = Vec::new();
+    let mut current: Vec<Vec2> = Vec::new();
+    let (mut pos, mut start) = (Vec2::ZERO, Vec2::ZERO);
+    // Last control point, reflected by the smooth curve commands
+    let mut last_control: Option<(char, Vec2)> = None;
+    let mut command = None;
+    let mut i = 0;
+
+    let numbers = |i: &mut usize, count: usize| -> Result<Vec<f32>, String> {
+        let mut out = Vec::with_capacity(count);
+        for _ in 0..count {
+            match tokens.get(*i) {
+                Some(PathToken::Number(n)) => out.push(*n),
+                _ => return Err(format!("expected {} numbers in path", count)),
+            }
+            *i += 1;
+        }
+        Ok(out)
+    };
+
+    while i < tokens.len() {
+        let c = match tokens[i] {
+            PathToken::Command(c) => {
+                i += 1;
+                c
+            }
+            // Numbers after a command's arguments repeat the command, after M they are lines
+            PathToken::Number(_) => match command {
+                Some('M') => 'L',
+                Some('m') => 'l',
+                Some(c) => c,
+                None => return Err(String::from("path must start with a command")),
+            },
+        };
+        command = Some(c);
+        let relative = c.is_ascii_lowercase();
+        let base = if relative { pos } else { Vec2::ZERO };
+        let point = |x: f32, y: f32| base + Vec2::new(x, y);
+        let mut control = None;
+        match c.to_ascii_uppercase() {
+            'M' => {
+                let n = numbers(&mut i, 2)?;
+                if current.len() > 1 {
+                    subpaths.push(std::mem::take(&mut current));
+                }
+                pos = point(n[0], n[1]);
+                start = pos;
+                current = vec![pos];
+            }
+            'L' => {
+                let n = numbers(&mut i, 2)?;
+                pos = point(n[0], n[1]);
+                current.push(pos);
+            }
+            'H' => {
+                let n = numbers(&mut i, 1)?;
+                pos.x = if relative { pos.x + n[0] } else { n[0] };
+                current.push(pos);
+            }
+            'V' => {
+                let n = numbers(&mut i, 1)?;
+                pos.y = if relative { pos.y + n[0] } else { n[0] };
+                current.push(pos);
+            }
+            'C' | 'S' => {
+                let (c1, n) = if c.eq_ignore_ascii_case(&'C') {
+                    let n = numbers(&mut i, 6)?;
+                    (point(n[0], n[1]), n[2..].to_vec())
+                } else {
+                    let reflected = match last_control {
+                        Some(('C', p)) => pos * 2.0 - p,
+                        _ => pos,
+                    };
+                    (reflected, numbers(&mut i, 4)?)
+                };
+                let (c2, end) = (point(n[0], n[1]), point(n[2], n[3]));
+                let curve = Bezier {
+                    start: pos,
+                    control1: c1,
+                    control2: c2,
+                    end,
+                    source: 0,
+                };
+                current.extend((1..=samples).map(|s| curve.point_at(s as f32 / samples as f32)));
+                control = Some(('C', c2));
+                pos = end;
+            }
+            'Q' | 'T' => {
+                let (q, end) = if c.eq_ignore_ascii_case(&'Q') {
+                    let n = numbers(&mut i, 4)?;
+                    (point(n[0], n[1]), point(n[2], n[3]))
+                } else {
+                    let reflected = match last_control {
+                        Some(('Q', p)) => pos * 2.0 - p,
+                        _ => pos,
+                    };
+                    let n = numbers(&mut i, 2)?;
+                    (reflected, point(n[0], n[1]))
+                };
+                let curve = Bezier {
+                    start: pos,
+                    control1: pos.lerp(q, 2.0 / 3.0),
+                    control2: end.lerp(q, 2.0 / 3.0),
+                    end,
+                    source: 0,
+                };
+                current.extend((1..=samples).map(|s| curve.point_at(s as f32 / samples as f32)));
+                control = Some(('Q', q));
+                pos = end;
+            }
+            'Z' => {
+                if current.len() > 1 {
+                    current.push(start);
+                    subpaths.push(std::mem::take(&mut current));
+                }
+                pos = start;
+                current = vec![pos];
+            }
+            _ => return Err(format!("unsupported path command {}", c)),
+        }
+        last_control = control;
+    }
+    if current.len() > 1 {
+        subpaths.push(current);
+    }
+    Ok(subpaths
+        .into_iter()
+        .map(|path| path.into_iter().map(flip).collect())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use nannou::prelude::{vec2, Vec2};
+
+    use super::parse_path;
+
+    fn assert_points(actual: &[Vec2], expected: &[(f32, f32)]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for (a, &(x, y)) in actual.iter().zip(expected) {
+            assert!(
+                a.distance(vec2(x, y)) < 1e-4,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn relative_commands_follow_the_pen() {
+        let paths = parse_path("m10 10 l5 0 v5 h-5 z m1 1 l1 0", 1).unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_points(
+            &paths[0],
+            &[
+                (10.0, -10.0),
+                (15.0, -10.0),
+                (15.0, -15.0),
+                (10.0, -15.0),
+                (10.0, -10.0),
+            ],
+        );
+        assert_points(&paths[1], &[(11.0, -11.0), (12.0, -11.0)]);
+    }
+
+    #[test]
+    fn numbers_after_a_move_draw_lines() {
+        let paths = parse_path("M0 0 10 0 10 10", 1).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_points(&paths[0], &[(0.0, 0.0), (10.0, 0.0), (10.0, -10.0)]);
+
+        let paths = parse_path("m1 1 2 0 0 2", 1).unwrap();
+        assert_points(&paths[0], &[(1.0, -1.0), (3.0, -1.0), (3.0, -3.0)]);
+    }
+
+    #[test]
+    fn smooth_cubic_reflects_the_last_control_point() {
+        let smooth = parse_path("M0 0 C0 10 10 10 10 0 S20 -10 20 0", 2).unwrap();
+        let explicit = parse_path("M0 0 C0 10 10 10 10 0 C10 -10 20 -10 20 0", 2).unwrap();
+        assert_points(
+            &smooth[0],
+            &[
+                (0.0, 0.0),
+                (5.0, -7.5),
+                (10.0, 0.0),
+                (15.0, 7.5),
+                (20.0, 0.0),
+            ],
+        );
+        assert_eq!(smooth, explicit);
+
+        // With no cubic before it there is nothing to reflect
+        let alone = parse_path("M10 0 S20 -10 20 0", 2).unwrap();
+        assert_points(&alone[0], &[(10.0, 0.0), (15.0, 3.75), (20.0, 0.0)]);
+    }
+
+    #[test]
+    fn smooth_quadratic_reflects_the_last_control_point() {
+        let smooth = parse_path("M0 0 Q5 10 10 0 T20 0", 2).unwrap();
+        let explicit = parse_path("M0 0 Q5 10 10 0 Q15 -10 20 0", 2).unwrap();
+        assert_points(
+            &smooth[0],
+            &[
+                (0.0, 0.0),
+                (5.0, -5.0),
+                (10.0, 0.0),
+                (15.0, 5.0),
+                (20.0, 0.0),
+            ],
+        );
+        assert_eq!(smooth, explicit);
+    }
+
+    #[test]
+    fn malformed_paths_are_errors() {
+        for d in [
+            "M1",
+            "10 10 L1 1",
+            "M0 0 X1 2",
+            "M0 0 L1 -",
+            "M1e 2",
+            "M0 0 C1 2 3 4",
+        ] {
+            assert!(parse_path(d, 4).is_err(), "{:?} parsed", d);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use nannou::prelude::Vec2;
+
+use crate::{segment::Segment, transform::Transform};
+
+/// Plane symmetry groups that can be tiled with a single motif placed at a lattice point
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Wallpaper {
+    /// Translations only
+    P1,
+    /// Half turns
+    P2,
+    /// Mirror across vertical lines
+    Pm,
+    /// Mirrors across horizontal and vertical lines
+    Pmm,
+    /// Quarter turns
+    P4,
+    /// Quarter turns and mirrors
+    P4m,
+    /// Third turns on a hexagonal lattice
+    P3,
+    /// Sixth turns on a hexagonal lattice
+    P6,
+    /// Sixth turns and mirrors on a hexagonal lattice
+    P6m,
+}
+
+impl Wallpaper {
+    /// The two vectors spanning a cell of the lattice with the given spacing
+    fn lattice(&self, spacing: f32) -> (Vec2, Vec2) {
+        match self {
+            Wallpaper::P3 | Wallpaper::P6 | Wallpaper::P6m => (
+                Vec2::new(spacing, 0.0),
+                Vec2::new(spacing * 0.5, spacing * 3.0_f32.sqrt() * 0.5),
+            ),
+            _ => (Vec2::new(spacing, 0.0), Vec2::new(0.0, spacing)),
+        }
+    }
+
+    /// Symmetries fixing a lattice point
+    fn point_group(&self) -> Vec<Transform> {
+        match self {
+            Wallpaper::P1 => cyclic(1),
+            Wallpaper::P2 => cyclic(2),
+            Wallpaper::Pm => vec![Transform::IDENTITY, Transform::mirror_x()],
+            Wallpaper::Pmm => dihedral(2),
+            Wallpaper::P4 => cyclic(4),
+            Wallpaper::P4m => dihedral(4),
+            Wallpaper::P3 => cyclic(3),
+            Wallpaper::P6 => cyclic(6),
+            Wallpaper::P6m => dihedral(6),
+        }
+    }
+}
+
+/// A group of transformations to replicate a drawing under
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Symmetry {
+    /// The given number of copies evenly rotated about the center
+    Rotation(usize),
+    /// The rotations plus a mirror image of each, the first mirror line being vertical
+    Dihedral(usize),
+    /// Copies of the drawing as a motif tiled over the plane, repeated the given number of
+    /// lattice cells out from the center in every direction
+    Wallpaper {
+        group: Wallpaper,
+        spacing: f32,
+        repeats: usize,
+    },
+}
+
+fn cyclic(n: usize) -> Vec<Transform> {
+    (0..n.max(1))
+        .map(|i| Transform::rotate(std::f32::consts::TAU * i as f32 / n.max(1) as f32))
+        .collect()
+}
+
+fn dihedral(n: usize) -> Vec<Transform> {
+    let rotations = cyclic(n);
+    let mirrored = rotations
+        .iter()
+        .map(|r| Transform::mirror_x().then(*r))
+        .collect::<Vec<Transform>>();
+    rotations.into_iter().chain(mirrored).collect()
+}
+
+impl Symmetry {
+    /// Every transformation in the group, about the origin
+    pub fn transforms(&self) -> Vec<Transform> {
+        match *self {
+            Symmetry::Rotation(n) => cyclic(n),
+            Symmetry::Dihedral(n) => dihedral(n),
+            Symmetry::Wallpaper {
+                group,
+                spacing,
+                repeats,
+            } => {
+                let (a, b) = group.lattice(spacing);
+                let r = repeats as i32;
+                let mut out = Vec::new();
+                for i in -r..=r {
+                    for j in -r..=r {
+                        let shift = Transform::translate(a * i as f32 + b * j as f32);
+                        out.extend(group.point_group().into_iter().map(|t| t.then(shift)));
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    /// Replicate the segments about the center and remove the duplicates this creates
+    pub fn apply(&self, segments: &[Segment], center: Vec2) -> Vec<Segment> {
+        dedupe(&self.replicate(segments, center), DEDUPE_TOLERANCE)
+    }
+
+    /// Replicate the segments about the center, then also merge collinear pieces that overlap.
+    /// This loses the drawing order but gives the cleanest output for plotting.
+    pub fn apply_merged(&self, segments: &[Segment], center: Vec2) -> Vec<Segment> {
+        merge_overlaps(&self.replicate(segments, center), DEDUPE_TOLERANCE)
+    }
+
+    /// Replicate the segments about the center, keeping every copy
+    pub fn replicate(&self, segments: &[Segment], center: Vec2) -> Vec<Segment> {
+        let to_origin = Transform::translate(-center);
+        let back = Transform::translate(center);
+        self.transforms()
+            .into_iter()
+            .flat_map(|t| {
+                let t = to_origin.then(t).then(back);
+                segments.iter().map(move |s| t.apply_segment(s))
+            })
+            .collect()
+    }
+}
+
+/// A line as its direction and offset from the origin, with the intervals along it covered by
+/// segments
+type Line = (Vec2, f32, Vec<(f32, f32)>);
+
+// Endpoints closer than this are treated as the same point
+const DEDUPE_TOLERANCE: f32 = 1e-2;
+// Lines whose directions differ by less than this many radians are treated as parallel
+const ANGLE_TOLERANCE: f32 = 1e-4;
+
+fn quantize(p: Vec2, tolerance: f32) -> (i64, i64) {
+    (
+        (p.x / tolerance).round() as i64,
+        (p.y / tolerance).round() as i64,
+    )
+}
+
+/// Remove segments that repeat an earlier one, in either direction, keeping the drawing order.
+/// A segment repeats another when both its ends are closer than the tolerance to the other's.
+pub fn dedupe(segments: &[Segment], tolerance: f32) -> Vec<Segment> {
+    // Segments kept so far under the cell of each of their ends. A point close to another can
+    // fall in the next cell over, so the cells around the start are all searched.
+    let mut cells: HashMap<(i64, i64), Vec<Segment>> = HashMap::new();
+    let mut out = Vec::new();
+    for s in segments {
+        if s.start().distance(s.end()) < tolerance {
+            continue;
+        }
+        let (x, y) = quantize(s.start(), tolerance);
+        let close = |a: Vec2, b: Vec2| a.distance(b) < tolerance;
+        let repeat = (-1..=1)
+            .flat_map(|i| (-1..=1).map(move |j| (x + i, y + j)))
+            .filter_map(|cell| cells.get(&cell))
+            .flatten()
+            .any(|t| {
+                (close(s.start(), t.start()) && close(s.end(), t.end()))
+                    || (close(s.start(), t.end()) && close(s.end(), t.start()))
+            });
+        if !repeat {
+            for end in [s.start(), s.end()] {
+                cells.entry(quantize(end, tolerance)).or_default().push(*s);
+            }
+            out.push(*s);
+        }
+    }
+    out
+}
+
+/// Merge collinear segments that overlap or touch end to end into single segments
+pub fn merge_overlaps(segments: &[Segment], tolerance: f32) -> Vec<Segment> {
+    // Each line is keyed by its direction, chosen to point rightward or up, and its offset from
+    // the origin. Segments are stored as intervals along that line. A line close to another can
+    // round to the next key over, so the keys around it are all searched. Near-vertical lines
+    // can be chosen to point up or down, so the keys half a turn round are searched as well,
+    // with the offset negated as the direction is.
+    let half_turn = (PI / ANGLE_TOLERANCE).round() as i64;
+    let mut keys: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    let mut lines: Vec<Line> = Vec::new();
+    for s in segments {
+        let mut d = s.direction();
+        if d == Vec2::ZERO {
+            continue;
+        }
+        if d.x < -ANGLE_TOLERANCE || (d.x.abs() <= ANGLE_TOLERANCE && d.y < 0.0) {
+            d = -d;
+        }
+        let offset = s.start().dot(d.perp());
+        let (a, o) = (
+            (d.y.atan2(d.x) / ANGLE_TOLERANCE).round() as i64,
+            (offset / tolerance).round() as i64,
+        );
+        let on = |(e, f, _): &Line| {
+            d.perp_dot(*e).abs() < ANGLE_TOLERANCE
+                && [s.start(), s.end()]
+                    .iter()
+                    .all(|p| (p.dot(e.perp()) - f).abs() < tolerance)
+        };
+        let found = [(a, o), (a - half_turn, -o), (a + half_turn, -o)]
+            .into_iter()
+            .flat_map(|(a, o)| (-1..=1).flat_map(move |i| (-1..=1).map(move |j| (a + i, o + j))))
+            .filter_map(|key| keys.get(&key))
+            .flatten()
+            .copied()
+            .find(|&i| on(&lines[i]));
+        let index = found.unwrap_or_else(|| {
+            keys.entry((a, o)).or_default().push(lines.len());
+            lines.push((d, offset, Vec::new()));
+            lines.len() - 1
+        });
+        let line = &mut lines[index];
+        let (t0, t1) = (s.start().dot(line.0), s.end().dot(line.0));
+        line.2.push((t0.min(t1), t0.max(t1)));
+    }
+
+    let mut out = Vec::new();
+    for (d, offset, mut intervals) in lines {
+        intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let n = d.perp();
+        let mut current = intervals[0];
+        for next in intervals.into_iter().skip(1) {
+            if next.0 <= current.1 + tolerance {
+                current.1 = current.1.max(next.1);
+            } else {
+                out.push(Segment::from((
+                    d * current.0 + n * offset,
+                    d * current.1 + n * offset,
+                )));
+                current = next;
+            }
+        }
+        out.push(Segment::from((
+            d * current.0 + n * offset,
+            d * current.1 + n * offset,
+        )));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(a: (f32, f32), b: (f32, f32)) -> Segment {
+        Segment::from((Vec2::new(a.0, a.1), Vec2::new(b.0, b.1)))
+    }
+
+    #[test]
+    fn rotation_dedupe() {
+        // A spoke has a copy for every turn, a line through the center only one for every half
+        let spoke = [segment((1.0, 0.0), (2.0, 0.0))];
+        assert_eq!(Symmetry::Rotation(4).apply(&spoke, Vec2::ZERO).len(), 4);
+        let diameter = [segment((-1.0, 0.0), (1.0, 0.0))];
+        assert_eq!(Symmetry::Rotation(4).apply(&diameter, Vec2::ZERO).len(), 2);
+        assert_eq!(Symmetry::Rotation(6).apply(&diameter, Vec2::ZERO).len(), 3);
+    }
+
+    #[test]
+    fn dihedral_dedupe() {
+        // A segment on the first mirror line is its own mirror image
+        let stem = [segment((0.0, 1.0), (0.0, 2.0))];
+        assert_eq!(Symmetry::Dihedral(4).replicate(&stem, Vec2::ZERO).len(), 8);
+        assert_eq!(Symmetry::Dihedral(4).apply(&stem, Vec2::ZERO).len(), 4);
+        // The sides of a square map onto each other
+        let side = [segment((-1.0, 1.0), (1.0, 1.0))];
+        assert_eq!(Symmetry::Dihedral(4).apply(&side, Vec2::ZERO).len(), 4);
+        // Anything else has a copy for every rotation and reflection
+        let leaf = [segment((0.5, 1.0), (1.0, 3.0))];
+        let center = Vec2::new(10.0, -5.0);
+        let leaf = [Transform::translate(center).apply_segment(&leaf[0])];
+        assert_eq!(Symmetry::Dihedral(5).apply(&leaf, center).len(), 10);
+    }
+
+    #[test]
+    fn dedupe_across_cell_edges() {
+        // Ends either side of the middle of a cell, which round to different cells
+        let a = segment((0.0049, 0.0), (1.0, 1.0));
+        let b = segment((1.0, 1.0049), (0.0051, 0.0));
+        assert_eq!(dedupe(&[a, b], 1e-2).len(), 1);
+        let far = segment((0.05, 0.0), (1.0, 1.0));
+        assert_eq!(dedupe(&[a, far], 1e-2).len(), 2);
+        assert!(dedupe(&[segment((1.0, 1.0), (1.001, 1.0))], 1e-2).is_empty());
+    }
+
+    #[test]
+    fn merge_across_key_edges() {
+        // Offsets either side of the middle of a key, which round to different keys
+        let a = segment((0.0, 0.0049), (1.0, 0.0049));
+        let b = segment((2.0, 0.0051), (0.5, 0.0051));
+        let merged = merge_overlaps(&[a, b], 1e-2);
+        assert_eq!(merged.len(), 1);
+        assert!((merged[0].length() - 2.0).abs() < 1e-3);
+        let apart = segment((0.0, 0.05), (2.0, 0.05));
+        assert_eq!(merge_overlaps(&[a, apart], 1e-2).len(), 2);
+    }
+
+    #[test]
+    fn merge_near_vertical_lines() {
+        // One points up just left of vertical and the other down just right of it, so they are
+        // keyed half a turn apart
+        let up = segment((0.0, 0.0), (-0.00005, 1.0));
+        let down = segment((-0.00005, 1.5), (0.000115, 0.0));
+        let merged = merge_overlaps(&[up, down], 1e-2);
+        assert_eq!(merged.len(), 1);
+        assert!((merged[0].length() - 1.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn apply_merged_joins_mirror_images() {
+        // Half a side of a square and its mirror image make the whole side
+        let half = [segment((0.0, 1.0), (1.0, 1.0))];
+        assert_eq!(Symmetry::Dihedral(4).apply(&half, Vec2::ZERO).len(), 8);
+        let merged = Symmetry::Dihedral(4).apply_merged(&half, Vec2::ZERO);
+        assert_eq!(merged.len(), 4);
+        assert!(merged.iter().all(|s| (s.length() - 2.0).abs() < 1e-3));
+        // A spoke and the one half a turn round make a diameter
+        let spoke = [segment((0.0, 0.0), (0.0, 2.0))];
+        let merged = Symmetry::Rotation(2).apply_merged(&spoke, Vec2::ZERO);
+        assert_eq!(merged.len(), 1);
+        assert!((merged[0].length() - 4.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,74 @@
+use std::f32::consts::TAU;
+
+/// A loop of fixed length for animations meant to repeat without a seam.
+///
+/// The period is rounded to a whole number of frames and time within the loop is given as a
+/// phase from 0 up to but not including 1, so the frame after the last is exactly the first.
+/// Drawing everything as a function of the phase keeps the loop seamless even when the period
+/// isn't a whole number of frames, at the cost of running very slightly fast or slow.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Timeline {
+    /// Length of the loop in seconds
+    pub period: f32,
+    pub fps: u32,
+}
+
+impl Timeline {
+    pub fn new(period: f32, fps: u32) -> Self {
+        Timeline {
+            period,
+            fps: fps.max(1),
+        }
+    }
+
+    /// Number of frames in one loop
+    pub fn frames(&self) -> u64 {
+        ((self.period * self.fps as f32).round() as u64).max(1)
+    }
+
+    /// Position of the frame within its loop, from 0 to just under 1
+    pub fn phase(&self, frame: u64) -> f32 {
+        let frames = self.frames();
+        ((frame % frames) as f64 / frames as f64) as f32
+    }
+
+    /// The phase as an angle, one loop being one full turn
+    pub fn angle(&self, frame: u64) -> f32 {
+        self.phase(frame) * TAU
+    }
+
+    /// Number of whole loops before the frame
+    pub fn loops(&self, frame: u64) -> u64 {
+        frame / self.frames()
+    }
+
+    /// True once the frame is past the end of the first loop, so capturing frames until this
+    /// is true records exactly one loop
+    pub fn is_complete(&self, frame: u64) -> bool {
+        frame >= self.frames()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_rounded_from_the_period() {
+        assert_eq!(Timeline::new(TAU, 30).frames(), 188);
+        assert_eq!(Timeline::new(2.0, 30).frames(), 60);
+        assert_eq!(Timeline::new(0.0, 30).frames(), 1);
+    }
+
+    #[test]
+    fn phase_wraps_exactly() {
+        let timeline = Timeline::new(TAU, 30);
+        let last = timeline.frames() - 1;
+        assert_eq!(timeline.phase(0), 0.0);
+        assert_eq!(timeline.phase(last + 1), 0.0);
+        assert!(timeline.phase(last) < 1.0);
+        assert_eq!(timeline.loops(last + 1), 1);
+        assert!(!timeline.is_complete(last));
+        assert!(timeline.is_complete(last + 1));
+    }
+}
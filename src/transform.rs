@@ -0,0 +1,213 @@
+use nannou::prelude::Vec2;
+
+use crate::segment::Segment;
+
+/// Affine transformation of the plane, a linear map given by where it sends the x and y axes
+/// followed by a translation
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub x_axis: Vec2,
+    pub y_axis: Vec2,
+    pub translation: Vec2,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        x_axis: Vec2::X,
+        y_axis: Vec2::Y,
+        translation: Vec2::ZERO,
+    };
+
+    pub fn translate(offset: Vec2) -> Self {
+        Transform {
+            translation: offset,
+            ..Transform::IDENTITY
+        }
+    }
+
+    /// Counterclockwise rotation about the origin
+    pub fn rotate(radians: f32) -> Self {
+        let (sin, cos) = radians.sin_cos();
+        Transform {
+            x_axis: Vec2::new(cos, sin),
+            y_axis: Vec2::new(-sin, cos),
+            translation: Vec2::ZERO,
+        }
+    }
+
+    pub fn rotate_degrees(degrees: f32) -> Self {
+        Transform::rotate(degrees.to_radians())
+    }
+
+    /// Counterclockwise rotation about the given point
+    pub fn rotate_about(radians: f32, center: Vec2) -> Self {
+        Transform::translate(-center)
+            .then(Transform::rotate(radians))
+            .then(Transform::translate(center))
+    }
+
+    pub fn scale(factor: f32) -> Self {
+        Transform::scale_xy(Vec2::splat(factor))
+    }
+
+    pub fn scale_xy(factors: Vec2) -> Self {
+        Transform {
+            x_axis: Vec2::new(factors.x, 0.0),
+            y_axis: Vec2::new(0.0, factors.y),
+            translation: Vec2::ZERO,
+        }
+    }
+
+    /// Shear so that x moves by kx * y and y moves by ky * x
+    pub fn shear(kx: f32, ky: f32) -> Self {
+        Transform {
+            x_axis: Vec2::new(1.0, ky),
+            y_axis: Vec2::new(kx, 1.0),
+            translation: Vec2::ZERO,
+        }
+    }
+
+    /// Reflection across the line through the origin with the given direction
+    pub fn mirror(direction: Vec2) -> Self {
+        let d = direction.normalize();
+        // Reflection matrix is 2 d d^T - I
+        Transform {
+            x_axis: Vec2::new(2.0 * d.x * d.x - 1.0, 2.0 * d.x * d.y),
+            y_axis: Vec2::new(2.0 * d.x * d.y, 2.0 * d.y * d.y - 1.0),
+            translation: Vec2::ZERO,
+        }
+    }
+
+    /// Reflection across the line through the point with the given direction
+    pub fn mirror_about(direction: Vec2, point: Vec2) -> Self {
+        Transform::translate(-point)
+            .then(Transform::mirror(direction))
+            .then(Transform::translate(point))
+    }
+
+    /// Flip left to right
+    pub fn mirror_x() -> Self {
+        Transform::mirror(Vec2::Y)
+    }
+
+    /// Flip top to bottom
+    pub fn mirror_y() -> Self {
+        Transform::mirror(Vec2::X)
+    }
+
+    /// Apply self and then other
+    pub fn then(self, other: Transform) -> Self {
+        Transform {
+            x_axis: other.apply_vector(self.x_axis),
+            y_axis: other.apply_vector(self.y_axis),
+            translation: other.apply(self.translation),
+        }
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.x_axis.perp_dot(self.y_axis)
+    }
+
+    /// True if the transform flips orientation, as a mirror does
+    pub fn is_reflection(&self) -> bool {
+        self.determinant() < 0.0
+    }
+
+    /// The transform that undoes this one, None if it collapses the plane
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det.abs() <= f32::EPSILON {
+            return None;
+        }
+        let x_axis = Vec2::new(self.y_axis.y, -self.x_axis.y) / det;
+        let y_axis = Vec2::new(-self.y_axis.x, self.x_axis.x) / det;
+        let linear = Transform {
+            x_axis,
+            y_axis,
+            translation: Vec2::ZERO,
+        };
+        Some(Transform {
+            translation: -linear.apply_vector(self.translation),
+            ..linear
+        })
+    }
+
+    pub fn apply(&self, p: Vec2) -> Vec2 {
+        self.apply_vector(p) + self.translation
+    }
+
+    /// Apply only the linear part, as is right for directions and offsets
+    pub fn apply_vector(&self, v: Vec2) -> Vec2 {
+        self.x_axis * v.x + self.y_axis * v.y
+    }
+
+    pub fn apply_segment(&self, s: &Segment) -> Segment {
+        Segment::from((self.apply(s.start()), self.apply(s.end())))
+    }
+
+    pub fn apply_segments(&self, segments: &[Segment]) -> Vec<Segment> {
+        segments.iter().map(|s| self.apply_segment(s)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use nannou::prelude::Vec2;
+
+    use super::Transform;
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        a.distance(b) < 1e-5
+    }
+
+    fn same(a: Transform, b: Transform) -> bool {
+        close(a.x_axis, b.x_axis)
+            && close(a.y_axis, b.y_axis)
+            && close(a.translation, b.translation)
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let transforms = [
+            Transform::rotate_about(0.7, Vec2::new(3.0, -2.0)),
+            Transform::scale_xy(Vec2::new(2.0, 0.5))
+                .then(Transform::translate(Vec2::new(1.0, 4.0))),
+            Transform::shear(0.3, -1.2),
+            Transform::mirror_about(Vec2::new(1.0, 2.0), Vec2::new(-5.0, 1.0)),
+        ];
+        for t in transforms {
+            let inverse = t.inverse().unwrap();
+            assert!(same(t.then(inverse), Transform::IDENTITY), "{:?}", t);
+            assert!(same(inverse.then(t), Transform::IDENTITY), "{:?}", t);
+        }
+    }
+
+    #[test]
+    fn flat_transform_has_no_inverse() {
+        assert_eq!(Transform::scale_xy(Vec2::new(1.0, 0.0)).inverse(), None);
+    }
+
+    #[test]
+    fn then_applies_self_first() {
+        let t = Transform::translate(Vec2::new(1.0, 0.0)).then(Transform::rotate(FRAC_PI_2));
+        assert!(close(t.apply(Vec2::ZERO), Vec2::new(0.0, 1.0)));
+        let t = Transform::rotate(FRAC_PI_2).then(Transform::translate(Vec2::new(1.0, 0.0)));
+        assert!(close(t.apply(Vec2::ZERO), Vec2::new(1.0, 0.0)));
+    }
+
+    #[test]
+    fn mirror_x_flips_left_to_right() {
+        let t = Transform::mirror_x();
+        assert!(close(t.apply(Vec2::new(2.0, 3.0)), Vec2::new(-2.0, 3.0)));
+        assert!(t.is_reflection());
+        assert!(same(t.then(t), Transform::IDENTITY));
+    }
+}
@@ -0,0 +1,334 @@
+use std::f32::consts::TAU;
+
+use nannou::{
+    color::{rgba, Rgba},
+    prelude::Vec2,
+};
+
+/// How a value moves from one keyframe to the next, as a function from 0 to 1 onto progress
+/// that starts at 0 and ends at 1
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// Springs past the end and settles
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    /// Lands at the end and bounces to rest
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    /// Pulls back a little before moving, or overshoots and comes back
+    BackIn,
+    BackOut,
+    BackInOut,
+}
+
+impl Easing {
+    /// Progress at the given point of the move, which is clamped to 0 to 1
+    pub fn ease(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => out(t, |t| t * t),
+            Easing::QuadInOut => in_out(t, |t| t * t),
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => out(t, |t| t * t * t),
+            Easing::CubicInOut => in_out(t, |t| t * t * t),
+            Easing::ElasticIn => elastic(t),
+            Easing::ElasticOut => out(t, elastic),
+            Easing::ElasticInOut => in_out(t, elastic),
+            Easing::BounceIn => out(t, bounce),
+            Easing::BounceOut => bounce(t),
+            Easing::BounceInOut => in_out(t, |t| out(t, bounce)),
+            Easing::BackIn => back(t),
+            Easing::BackOut => out(t, back),
+            Easing::BackInOut => in_out(t, back),
+        }
+    }
+}
+
+/// The easing run backwards, so it eases out where the original eases in
+fn out(t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    1.0 - ease_in(1.0 - t)
+}
+
+/// The easing in over the first half and out over the second
+fn in_out(t: f32, ease_in: impl Fn(f32) -> f32) -> f32 {
+    if t < 0.5 {
+        ease_in(t * 2.0) / 2.0
+    } else {
+        1.0 - ease_in((1.0 - t) * 2.0) / 2.0
+    }
+}
+
+fn elastic(t: f32) -> f32 {
+    if t <= 0.0 || t >= 1.0 {
+        return t;
+    }
+    // Three swings growing to the end
+    -(2.0f32.powf(10.0 * (t - 1.0))) * ((t - 1.075) * TAU / 0.3).sin()
+}
+
+fn back(t: f32) -> f32 {
+    // Pulls back by about a tenth
+    const S: f32 = 1.70158;
+    t * t * ((S + 1.0) * t - S)
+}
+
+fn bounce(t: f32) -> f32 {
+    // A fall followed by three bounces, each a parabola
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Values that can be blended, so they can be animated between keyframes
+pub trait Tweenable: Copy {
+    /// The value the given fraction of the way to the other. Fractions outside 0 to 1 carry on
+    /// past the ends, which elastic and back easings rely on.
+    fn tween(self, other: Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn tween(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Tweenable for Vec2 {
+    fn tween(self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+}
+
+impl Tweenable for Rgba {
+    fn tween(self, other: Self, t: f32) -> Self {
+        rgba(
+            self.red.tween(other.red, t),
+            self.green.tween(other.green, t),
+            self.blue.tween(other.blue, t),
+            self.alpha.tween(other.alpha, t),
+        )
+    }
+}
+
+/// What a track does once it reaches its last keyframe
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Stay at the last keyframe
+    Once,
+    /// Jump back to the first keyframe and play again
+    Repeat,
+    /// Play backwards to the first keyframe, then forwards again
+    Yoyo,
+}
+
+/// A value at a time, reached from the keyframe before with the easing
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Keyframe<T> {
+    /// Seconds from the start of the track
+    pub time: f32,
+    pub value: T,
+    pub easing: Easing,
+}
+
+/// Keyframes of a value over time, sampled at any time in seconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+    pub mode: Mode,
+    /// Number of plays before stopping, each way counting as one for a yoyo. None plays forever.
+    pub plays: Option<u32>,
+}
+
+impl<T: Tweenable> Track<T> {
+    /// A track that holds the value from time 0 until keyframes are added
+    pub fn new(value: T) -> Self {
+        Track {
+            keyframes: vec![Keyframe {
+                time: 0.0,
+                value,
+                easing: Easing::Linear,
+            }],
+            mode: Mode::Once,
+            plays: None,
+        }
+    }
+
+    /// Add a keyframe the given number of seconds after the last one
+    pub fn to(mut self, duration: f32, value: T, easing: Easing) -> Self {
+        let time = self.duration() + duration.max(0.0);
+        self.keyframes.push(Keyframe {
+            time,
+            value,
+            easing,
+        });
+        self
+    }
+
+    /// Hold the last value for the given number of seconds
+    pub fn hold(self, duration: f32) -> Self {
+        let value = self.keyframes[self.keyframes.len() - 1].value;
+        self.to(duration, value, Easing::Linear)
+    }
+
+    /// Play from the start again after the end
+    pub fn repeat(mut self) -> Self {
+        self.mode = Mode::Repeat;
+        self
+    }
+
+    /// Play back and forth
+    pub fn yoyo(mut self) -> Self {
+        self.mode = Mode::Yoyo;
+        self
+    }
+
+    /// Stop after the given number of plays
+    pub fn plays(mut self, plays: u32) -> Self {
+        self.plays = Some(plays.max(1));
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// Seconds from the first keyframe to the last
+    pub fn duration(&self) -> f32 {
+        self.keyframes[self.keyframes.len() - 1].time
+    }
+
+    /// Seconds until the track stops changing, None if it never does
+    pub fn total(&self) -> Option<f32> {
+        match (self.mode, self.plays) {
+            (Mode::Once, _) => Some(self.duration()),
+            (_, Some(plays)) => Some(self.duration() * plays as f32),
+            (_, None) => None,
+        }
+    }
+
+    /// Time within a single play of the keyframes for the time since the start
+    fn local(&self, time: f32) -> f32 {
+        let duration = self.duration();
+        let time = match self.total() {
+            Some(total) => time.clamp(0.0, total),
+            None => time.max(0.0),
+        };
+        if duration <= 0.0 || self.mode == Mode::Once {
+            return time.min(duration);
+        }
+        // The last moment of the last play belongs to that play, not the one after
+        let play = ((time / duration).floor() as u32).min(self.plays.unwrap_or(u32::MAX) - 1);
+        let within = time - play as f32 * duration;
+        match self.mode {
+            Mode::Yoyo if play % 2 == 1 => duration - within,
+            _ => within,
+        }
+    }
+
+    pub fn sample(&self, time: f32) -> T {
+        let time = self.local(time);
+        let next = self
+            .keyframes
+            .iter()
+            .position(|k| k.time > time)
+            .unwrap_or(self.keyframes.len() - 1)
+            .max(1)
+            .min(self.keyframes.len() - 1);
+        if next == 0 {
+            return self.keyframes[0].value;
+        }
+        let (a, b) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let span = b.time - a.time;
+        let t = if span > 0.0 {
+            (time - a.time) / span
+        } else {
+            1.0
+        };
+        a.value.tween(b.value, b.easing.ease(t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 16] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+    ];
+
+    #[test]
+    fn easings_start_and_end_in_place() {
+        for easing in ALL {
+            assert!(easing.ease(0.0).abs() < 1e-3, "{:?} at 0", easing);
+            assert!((easing.ease(1.0) - 1.0).abs() < 1e-3, "{:?} at 1", easing);
+        }
+        assert!(Easing::BackIn.ease(0.2) < 0.0);
+        assert!(Easing::ElasticOut.ease(0.2) > 1.0);
+        assert_eq!(Easing::QuadInOut.ease(0.5), 0.5);
+        assert!((Easing::BounceOut.ease(1.0 / 2.75) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tracks_repeat_and_yoyo() {
+        let track = Track::new(0.0).to(2.0, 10.0, Easing::Linear);
+        assert_eq!(track.sample(-1.0), 0.0);
+        assert_eq!(track.sample(1.0), 5.0);
+        assert_eq!(track.sample(5.0), 10.0);
+        assert_eq!(track.clone().repeat().sample(5.0), 5.0);
+
+        let yoyo = track.yoyo().plays(3);
+        assert_eq!(yoyo.sample(2.5), 7.5);
+        assert_eq!(yoyo.sample(4.0), 0.0);
+        assert_eq!(yoyo.sample(5.0), 5.0);
+        assert_eq!(yoyo.sample(60.0), 10.0);
+        assert_eq!(yoyo.total(), Some(6.0));
+    }
+
+    #[test]
+    fn tracks_ease_each_keyframe() {
+        let track = Track::new(Vec2::ZERO)
+            .to(1.0, Vec2::new(10.0, 0.0), Easing::QuadIn)
+            .hold(1.0)
+            .to(1.0, Vec2::new(10.0, 10.0), Easing::QuadOut);
+        assert_eq!(track.duration(), 3.0);
+        assert_eq!(track.sample(0.5), Vec2::new(2.5, 0.0));
+        assert_eq!(track.sample(1.5), Vec2::new(10.0, 0.0));
+        assert_eq!(track.sample(2.5), Vec2::new(10.0, 7.5));
+    }
+}
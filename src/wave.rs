@@ -1,11 +1,221 @@
+use std::f32::consts::PI;
+
 use itertools_num::linspace;
 use nannou::prelude::*;
 
-use crate::capture::captured_frame_path;
+use crate::{
+    audio::{Binding, Signals, Source},
+    capture::Session,
+    clock::Clock,
+    encode::{Encoding, Format},
+    epicycle::{self, Epicycle},
+    run::{Params, Run},
+    timeline::Timeline,
+};
+
+/// Periodic signals drawn as a sum of harmonics
+#[derive(Debug, Clone, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Sawtooth,
+    Triangle,
+    /// Sine and cosine coefficients of each harmonic, starting with the first
+    Custom(Vec<(f32, f32)>),
+}
+
+impl Waveform {
+    /// Sine and cosine coefficients of the kth harmonic, k starting at 1
+    pub fn coefficients(&self, k: usize) -> (f32, f32) {
+        let kf = k as f32;
+        let odd = k % 2 == 1;
+        match self {
+            Waveform::Sine => (if k == 1 { 1.0 } else { 0.0 }, 0.0),
+            Waveform::Square if odd => (4.0 / (PI * kf), 0.0),
+            Waveform::Sawtooth => {
+                let sign = if odd { 1.0 } else { -1.0 };
+                (sign * 2.0 / (PI * kf), 0.0)
+            }
+            Waveform::Triangle if odd => {
+                let sign = if k % 4 == 1 { 1.0 } else { -1.0 };
+                (sign * 8.0 / (PI * PI * kf * kf), 0.0)
+            }
+            Waveform::Custom(coefficients) => {
+                coefficients.get(k - 1).copied().unwrap_or((0.0, 0.0))
+            }
+            _ => (0.0, 0.0),
+        }
+    }
 
-pub fn view(app: &App, frame: Frame) {
-    if frame.nth() > 188 {
-        app.quit()
+    /// One circle per harmonic up to the given number, each tracing the harmonic in its height.
+    /// Harmonics that are zero are left out.
+    pub fn epicycles(&self, harmonics: usize, scale: f32) -> Vec<Epicycle> {
+        (1..=harmonics)
+            .filter_map(|k| {
+                // b sin(kt) + a cos(kt) is the height of a circle of radius hypot(a, b)
+                let (b, a) = self.coefficients(k);
+                let radius = a.hypot(b) * scale;
+                (radius > 0.0).then(|| Epicycle {
+                    frequency: k as f32,
+                    radius,
+                    phase: a.atan2(b),
+                })
+            })
+            .collect()
+    }
+
+    /// The built in waveform with the name
+    pub fn named(name: &str) -> Option<Waveform> {
+        match name {
+            "sine" => Some(Waveform::Sine),
+            "square" => Some(Waveform::Square),
+            "sawtooth" => Some(Waveform::Sawtooth),
+            "triangle" => Some(Waveform::Triangle),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "sine",
+            Waveform::Square => "square",
+            Waveform::Sawtooth => "sawtooth",
+            Waveform::Triangle => "triangle",
+            Waveform::Custom(_) => "custom",
+        }
+    }
+
+    /// The next built in waveform, custom coefficients go back to a sine
+    fn next(&self) -> Waveform {
+        match self {
+            Waveform::Sine => Waveform::Square,
+            Waveform::Square => Waveform::Sawtooth,
+            Waveform::Sawtooth => Waveform::Triangle,
+            Waveform::Triangle | Waveform::Custom(_) => Waveform::Sine,
+        }
+    }
+}
+
+const MAX_HARMONICS: usize = 100;
+
+pub struct Model {
+    clock: Clock,
+    timeline: Timeline,
+    capture: Session,
+    /// Quit once the first loop has been drawn
+    once: bool,
+    pub waveform: Waveform,
+    pub harmonics: usize,
+    /// Soundtrack the size of the circles follows, from the WAV file named by AUDIO
+    audio: Option<(Signals, Binding)>,
+}
+
+impl Model {
+    /// The settings on screen, for the next recording
+    fn run(&self) -> Run {
+        Run::new(
+            "wave",
+            Params::Wave {
+                waveform: self.waveform.clone(),
+                harmonics: self.harmonics,
+                audio: self.audio.as_ref().and_then(|(s, _)| s.path.clone()),
+            },
+        )
+    }
+}
+
+fn follow(signals: Signals) -> (Signals, Binding) {
+    (signals, Binding::new(Source::Amplitude, 30.0, 90.0))
+}
+
+fn audio() -> Option<(Signals, Binding)> {
+    Signals::from_env(30, 8).map(follow)
+}
+
+/// Record one loop of a single turning circle and quit
+pub fn model(_app: &App) -> Model {
+    once(Waveform::Sine, 1, audio())
+}
+
+/// Record one loop of the waveform and quit
+fn once(waveform: Waveform, harmonics: usize, audio: Option<(Signals, Binding)>) -> Model {
+    let mut model = Model {
+        // Every frame is recorded so time has to step evenly however slow the capture is
+        clock: Clock::new(30).offline(),
+        // One turn of the circle
+        timeline: Timeline::new(TAU, 30),
+        // Recording starts straight away to capture exactly one loop
+        capture: Session::new("wave")
+            .encode(Format::Gif, Encoding::default())
+            .recording(),
+        once: true,
+        waveform,
+        harmonics,
+        audio,
+    };
+    model.capture.run = Some(model.run());
+    model
+}
+
+/// Record one loop with the settings of an earlier run and quit
+pub fn replay(run: &Run) -> Result<Model, String> {
+    match &run.params {
+        Params::Wave {
+            waveform,
+            harmonics,
+            audio,
+        } => {
+            let audio = match audio {
+                Some(path) => Some(follow(Signals::load(path, 30, 8)?)),
+                None => None,
+            };
+            Ok(once(waveform.clone(), *harmonics, audio))
+        }
+        _ => Err(format!("{} is not a wave run", run.sketch)),
+    }
+}
+
+/// Run until closed with the waveform and number of harmonics changed by the keys.
+/// W switches waveform, up and down change the number of harmonics and R records.
+pub fn live(waveform: Waveform, harmonics: usize) -> Model {
+    let mut model = Model {
+        clock: Clock::from_env(30),
+        timeline: Timeline::new(TAU, 30),
+        capture: Session::new("wave").encode(Format::Gif, Encoding::default()),
+        once: false,
+        waveform,
+        harmonics,
+        audio: audio(),
+    };
+    model.capture.run = Some(model.run());
+    model
+}
+
+pub fn update(_app: &App, model: &mut Model, update: Update) {
+    model.clock.tick(&update);
+}
+
+pub fn event(_app: &App, model: &mut Model, event: Event) {
+    if let Event::WindowEvent {
+        simple: Some(WindowEvent::KeyPressed(key)),
+        ..
+    } = event
+    {
+        match key {
+            Key::Up => model.harmonics = (model.harmonics + 1).min(MAX_HARMONICS),
+            Key::Down => model.harmonics = model.harmonics.saturating_sub(1).max(1),
+            Key::W => model.waveform = model.waveform.next(),
+            _ => return,
+        }
+        model.capture.run = Some(model.run());
+    }
+}
+
+pub fn view(app: &App, model: &Model, frame: Frame) {
+    if model.once && model.timeline.is_complete(model.clock.frame()) {
+        model.capture.stop(app);
+        app.quit();
+        return;
     }
 
     let draw = app.draw();
@@ -14,34 +224,51 @@ pub fn view(app: &App, frame: Frame) {
     draw.background().color(PLUM);
     draw.rect().w_h(800.0, 400.0).color(BLACK);
 
-    // Set the animation's time based on the frame
-    let t = (frame.nth() as f32) / 30.0;
+    let t = model.timeline.angle(model.clock.frame());
 
-    // Set the overall scale ofthe circle and draw it.
-    let scale = 50.0;
+    // Set the overall scale of the first circle and draw it.
+    let scale = match &model.audio {
+        Some((signals, binding)) => binding.value(signals, model.clock.frame()),
+        None => 50.0,
+    };
+    let epicycles = model.waveform.epicycles(model.harmonics, scale);
     draw.ellipse().w_h(scale * 2.0, scale * 2.0).color(GRAY);
+    if epicycles.len() > 1 {
+        epicycle::draw_chain(&draw, &epicycles, Vec2::ZERO, t, rgba(0.8, 0.8, 0.8, 0.6));
+    }
 
-    // Create the dot that will move around the circle and the bar connected to it
-    let x = t.cos() * scale;
-    let y = t.sin() * scale;
-    let moving_dot = pt2(x, y);
+    // Create the dot that moves at the end of the chain and the bar connected to it
+    let moving_dot = epicycle::tip(&epicycles, Vec2::ZERO, t);
+    let y = moving_dot.y;
 
     draw.line()
         .start(moving_dot)
         .end((150.0, y).into())
         .weight(3.0)
         .color(SALMON);
-    draw.ellipse().w_h(10.0, 10.0).x_y(x, y);
+    draw.ellipse().w_h(10.0, 10.0).xy(moving_dot);
 
+    // The wave shows the height of the tip ahead in time, moving left into the bar
     let wave = linspace::<f32>(150.0, 400.0, 200).map(|n| {
-        let pt = pt2(n, ((0.1 * (n - 150.0)) + t).sin() * scale);
-        (pt, STEELBLUE)
+        let ahead = epicycle::tip(&epicycles, Vec2::ZERO, t + 0.1 * (n - 150.0));
+        (pt2(n, ahead.y), STEELBLUE)
     });
     draw.polyline().weight(3.0).points_colored(wave);
 
     draw.ellipse().w_h(10.0, 10.0).x_y(150.0, y);
 
+    if !model.once {
+        draw.text(&format!(
+            "{} wave, {} harmonics",
+            model.waveform.name(),
+            model.harmonics
+        ))
+        .xy(pt2(275.0, -170.0))
+        .w(250.0)
+        .font_size(14)
+        .color(WHITE);
+    }
+
     draw.to_frame(app, &frame).unwrap();
-    app.main_window()
-        .capture_frame(captured_frame_path(&app, &frame, "wave"));
+    model.capture.capture(app);
 }